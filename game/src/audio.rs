@@ -1,99 +1,657 @@
-use bevy::prelude::*;
-use bevy_kira_audio::prelude::*;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use super::{GameState, Volume};
+use bevy::{asset::io::file::FileAssetReader, ecs::system::SystemParam, prelude::*};
+use bevy_kira_audio::{prelude::*, AudioSource};
+use rand::Rng;
+
+use crate::{
+    animator::Animation,
+    environment::{Spawner, Zombie},
+    events::{PlayerHitByEnemy, PlayerReachedFlag, SpawnerActivated},
+    level::CustomLevel,
+    loading::GameAssets,
+    logging::AUDIO,
+    player::{is_immune, Dash, GodMode, PlayerStatus},
+    GameState, MasterVolume, MusicVolume, Muted, PlayerFlag, SfxVolume, UiVolume, Volume,
+};
+
+// Files each track can be played from, in order of preference. A track is silent when none of
+// them exists
+pub const MENU_MUSIC: &[&str] = &["audios/bip-bop.ogg"];
+pub const GAME_MUSIC: &[&str] = &["audios/ganxta.ogg", "audios/bip-bop.ogg"];
+pub const SPLASH_JINGLE: &[&str] = &["audios/reward.wav"];
+pub const HIT_SOUND: &[&str] = &["audios/hit.wav"];
+pub const REWARD_SOUND: &[&str] = &["audios/reward.wav"];
+pub const FOOTSTEP_SOUND: &[&str] = &["audios/footstep.wav"];
+pub const CLICK_SOUND: &[&str] = &["audios/click.wav"];
+pub const PULSE_LAYER: &[&str] = &["audios/layer-pulse.wav"];
+pub const DRONE_LAYER: &[&str] = &["audios/layer-drone.wav"];
+pub const ZOMBIE_GROAN: &[&str] = &["audios/zombie-groan.wav"];
+pub const SPAWNER_SOUND: &[&str] = &["audios/spawn.wav"];
+
+// Every track of the game, by name
+pub const TRACKS: [(&str, &[&str]); 11] = [
+    ("menu music", MENU_MUSIC),
+    ("in-game music", GAME_MUSIC),
+    ("splash jingle", SPLASH_JINGLE),
+    ("hit sound", HIT_SOUND),
+    ("reward sound", REWARD_SOUND),
+    ("footstep sound", FOOTSTEP_SOUND),
+    ("click sound", CLICK_SOUND),
+    ("pulse layer", PULSE_LAYER),
+    ("drone layer", DRONE_LAYER),
+    ("zombie groan", ZOMBIE_GROAN),
+    ("spawner sound", SPAWNER_SOUND),
+];
+
+// Sounds of zombies and spawners fade out with their distance to the player, and can't be heard
+// at all past this one
+const HEARING_DISTANCE: f32 = 900.0;
+
+// Zombies groan every so often, at random so that a crowd does not groan in unison
+const GROAN_INTERVAL: std::ops::RangeInclusive<f32> = 4.0..=10.0;
+
+// Zombies start raising the intensity of the music within `FAR` of the player, and push it to the
+// maximum within `NEAR`
+const INTENSITY_FAR: f32 = 400.0;
+const INTENSITY_NEAR: f32 = 80.0;
+
+// Fastest the intensity can change, per second, so that layers swell rather than pop
+const INTENSITY_RATE: f32 = 0.8;
+
+// Frames of a walking animation on which a foot touches the ground
+const FOOTSTEP_FRAMES: [usize; 2] = [1, 5];
+
+// Folder the asset server reads from
+pub fn assets_dir() -> PathBuf {
+    FileAssetReader::get_base_path().join("assets")
+}
+
+// First file of `candidates` found in `assets_dir`. Falling back to another file, or to silence,
+// is reported since the asset server would otherwise fail without a word
+pub fn find_track<'a>(name: &str, candidates: &[&'a str], assets_dir: &Path) -> Option<&'a str> {
+    let found = candidates
+        .iter()
+        .copied()
+        .find(|path| assets_dir.join(path).is_file());
+    match found {
+        Some(path) if Some(&path) != candidates.first() => warn!(
+            target: AUDIO,
+            "Missing {} for the {name}, playing {path} instead",
+            candidates[0]
+        ),
+        Some(_) => {}
+        None => error!(
+            target: AUDIO,
+            "No file found for the {name} in {} (tried {}), it will stay silent",
+            assets_dir.display(),
+            candidates.join(", ")
+        ),
+    }
+    found
+}
 
 pub struct GameAudioPlugin;
 
+// Menu and in-game music, faded in and out by the `MusicDirector`
+#[derive(Resource)]
+struct Music;
+
+// Layers played over the in-game music, louder as the intensity rises
 #[derive(Resource)]
-struct Menu;
+struct PulseLayer;
 
 #[derive(Resource)]
-struct InGame;
+struct DroneLayer;
 
 #[derive(Resource)]
 struct Splash;
 
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
-pub enum BGMInitialized {
-    Yes,
-    No,
+// Short sounds played over the music
+#[derive(Resource)]
+struct Sfx;
+
+// Sounds of the menus
+#[derive(Resource)]
+struct UiSounds;
+
+// Volume every channel plays at, once the master volume and mute are applied. The music channels
+// are bound to the music bus, `Sfx` to the SFX bus and `UiSounds` to the UI bus
+#[derive(SystemParam)]
+pub struct Mixer<'w> {
+    master: Res<'w, MasterVolume>,
+    music: Res<'w, MusicVolume>,
+    sfx: Res<'w, SfxVolume>,
+    ui: Res<'w, UiVolume>,
+    muted: Res<'w, Muted>,
+}
+
+impl Mixer<'_> {
+    fn bus(&self, volume: Volume) -> f64 {
+        if self.muted.0 {
+            0.0
+        } else {
+            self.master.get_val() * volume.get_val()
+        }
+    }
+
+    pub fn music(&self) -> f64 {
+        self.bus(**self.music)
+    }
+
+    pub fn sfx(&self) -> f64 {
+        self.bus(**self.sfx)
+    }
+
+    pub fn ui(&self) -> f64 {
+        self.bus(**self.ui)
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.master.is_changed()
+            || self.music.is_changed()
+            || self.sfx.is_changed()
+            || self.ui.is_changed()
+            || self.muted.is_changed()
+    }
+}
+
+// How long the music takes to fade in and out when the game moves to another state
+#[derive(Resource, Debug, PartialEq, Clone, Copy)]
+pub struct MusicFades {
+    pub fade_in: Duration,
+    pub fade_out: Duration,
+}
+
+impl Default for MusicFades {
+    fn default() -> Self {
+        Self {
+            fade_in: Duration::from_millis(1500),
+            fade_out: Duration::from_millis(1000),
+        }
+    }
+}
+
+// Playlist of the current state and the track of it currently played on the music channel. The
+// tracks of a playlist follow each other, a playlist of a single track is looped. The same playlist
+// keeps playing from one state to the next rather than being restarted
+#[derive(Resource, Default)]
+pub struct MusicDirector {
+    playlist: Vec<(String, Handle<AudioSource>)>,
+    next: usize,
+    playing: Option<(String, Handle<AudioInstance>)>,
+}
+
+impl MusicDirector {
+    pub fn playing(&self) -> Option<&str> {
+        self.playing.as_ref().map(|(path, _)| path.as_str())
+    }
+
+    fn plays(&self, playlist: &[(String, Handle<AudioSource>)]) -> bool {
+        self.playlist.len() == playlist.len()
+            && self
+                .playlist
+                .iter()
+                .zip(playlist)
+                .all(|((playing, _), (path, _))| playing == path)
+    }
+
+    // Fades the current track out and the first track of `playlist` in, unless that playlist is
+    // already playing. Returns the track faded out
+    pub fn crossfade_to<T: Resource>(
+        &mut self,
+        playlist: Vec<(String, Handle<AudioSource>)>,
+        audio: &AudioChannel<T>,
+        instances: &mut Assets<AudioInstance>,
+        fades: &MusicFades,
+    ) -> Option<String> {
+        if self.plays(&playlist) {
+            return None;
+        }
+
+        let faded_out = self.playing.take().map(|(path, instance)| {
+            debug!(target: AUDIO, "Fading out {path}");
+            if let Some(instance) = instances.get_mut(&instance) {
+                instance.stop(AudioTween::linear(fades.fade_out));
+            }
+            path
+        });
+        self.playlist = playlist;
+        self.next = 0;
+        self.play_next(audio, fades);
+        faded_out
+    }
+
+    fn play_next<T: Resource>(&mut self, audio: &AudioChannel<T>, fades: &MusicFades) {
+        let Some((path, music)) = self.playlist.get(self.next).cloned() else {
+            return;
+        };
+        debug!(target: AUDIO, "Fading in {path}");
+        let mut command = audio.play(music);
+        command.fade_in(AudioTween::linear(fades.fade_in));
+        if self.playlist.len() == 1 {
+            command.looped();
+        }
+        self.playing = Some((path, command.handle()));
+        self.next = (self.next + 1) % self.playlist.len();
+    }
+}
+
+// Sounds played from an entity, panned and attenuated around the player as either of them moves
+#[derive(Component, Default)]
+struct Emitter {
+    instances: Vec<Handle<AudioInstance>>,
+}
+
+// Time left before a zombie groans again
+#[derive(Component, Deref, DerefMut)]
+struct Groan(Timer);
+
+impl Groan {
+    fn random() -> Self {
+        let delay = rand::thread_rng().gen_range(GROAN_INTERVAL);
+        Self(Timer::from_seconds(delay, TimerMode::Once))
+    }
+}
+
+// How tense the game is, from 0 when no zombie is around to 1 when one is about to catch the player
+#[derive(Resource, Debug, Default, PartialEq, Clone, Copy)]
+pub struct Intensity(pub f32);
+
+// Intensity zombies at `distance` from the player call for
+pub fn threat(distance: f32) -> f32 {
+    ((INTENSITY_FAR - distance) / (INTENSITY_FAR - INTENSITY_NEAR)).clamp(0.0, 1.0)
+}
+
+// Gain and panning of a sound played at `emitter` as the player hears it from `listener`. The gain
+// falls off with the square of the distance, the panning goes from 0 on the left to 1 on the right
+pub fn spatial_gain(listener: Vec2, emitter: Vec2) -> (f64, f64) {
+    let path = emitter - listener;
+    let gain = (1.0 - path.length() / HEARING_DISTANCE)
+        .clamp(0.0, 1.0)
+        .powi(2);
+    let panning = (path.normalize_or_zero().x + 1.0) / 2.0;
+    (gain as f64, panning as f64)
 }
 
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
-pub enum GMInitialized {
-    Yes,
-    No,
+// Gain of a layer fading in as the intensity goes from `start` to `end`
+pub fn layer_gain(intensity: f32, start: f32, end: f32) -> f64 {
+    ((intensity - start) / (end - start)).clamp(0.0, 1.0) as f64
 }
 
 impl Plugin for GameAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(AudioPlugin)
-            .insert_resource(BGMInitialized::No)
-            .insert_resource(GMInitialized::No)
-            .add_audio_channel::<Menu>()
-            .add_audio_channel::<InGame>()
+            .init_resource::<MusicFades>()
+            .init_resource::<MusicDirector>()
+            .init_resource::<Intensity>()
+            .add_audio_channel::<Music>()
+            .add_audio_channel::<PulseLayer>()
+            .add_audio_channel::<DroneLayer>()
             .add_audio_channel::<Splash>()
-            .add_systems(Update, start_bgm.run_if(in_state(GameState::Menu)))
-            .add_systems(Update, stop_bgm.run_if(in_state(GameState::Game)))
-            .add_systems(Update, start_ingame.run_if(in_state(GameState::Game)))
-            .add_systems(Update, stop_ingame.run_if(in_state(GameState::Menu)))
-            .add_systems(OnEnter(GameState::Splash), play_splash);
+            .add_audio_channel::<Sfx>()
+            .add_audio_channel::<UiSounds>()
+            .add_systems(Update, apply_volumes.run_if(mixer_changed))
+            .add_systems(OnEnter(GameState::Splash), direct_music)
+            .add_systems(OnEnter(GameState::Menu), direct_music)
+            .add_systems(OnEnter(GameState::Game), (direct_music, start_layers))
+            .add_systems(OnExit(GameState::Game), stop_layers)
+            .add_systems(Update, advance_playlist)
+            .add_systems(
+                Update,
+                (measure_intensity, apply_layer_volumes)
+                    .chain()
+                    .run_if(in_state(GameState::Game)),
+            )
+            .add_systems(OnEnter(GameState::Splash), play_splash)
+            .add_systems(
+                Update,
+                (play_hit, play_reward, play_footsteps).run_if(in_state(GameState::Game)),
+            )
+            .add_systems(
+                Update,
+                (attach_emitters, play_groans, play_spawners, place_sounds)
+                    .run_if(in_state(GameState::Game)),
+            )
+            .add_systems(Update, play_clicks);
     }
 }
 
-fn play_splash(
-    asset_server: Res<AssetServer>,
-    audio: Res<AudioChannel<Splash>>,
-    volume: Res<Volume>,
+fn play_splash(assets: Res<GameAssets>, audio: Res<AudioChannel<Splash>>, mixer: Mixer) {
+    let Some((_, jingle)) = &assets.splash_jingle else {
+        return;
+    };
+    debug!(target: AUDIO, "Playing the splash jingle");
+    audio.play(jingle.clone()).with_volume(mixer.music());
+}
+
+// Playlist of the level about to be played, if it picked one. Tracks whose file is missing are
+// left out
+fn level_playlist(
+    level: Option<&CustomLevel>,
+    server: &AssetServer,
+) -> Vec<(String, Handle<AudioSource>)> {
+    let Some(level) = level else {
+        return Vec::new();
+    };
+    let assets_dir = assets_dir();
+    level
+        .0
+        .music
+        .iter()
+        .filter_map(|music| find_track("level music", &[music.as_str()], &assets_dir))
+        .map(|path| (path.to_string(), server.load(path.to_string())))
+        .collect()
+}
+
+// Crossfades to the playlist of the state just entered, once per transition. Levels without a
+// playlist of their own play the in-game music
+#[allow(clippy::too_many_arguments)]
+fn direct_music(
+    state: Res<State<GameState>>,
+    assets: Res<GameAssets>,
+    level: Option<Res<CustomLevel>>,
+    server: Res<AssetServer>,
+    fades: Res<MusicFades>,
+    audio: Res<AudioChannel<Music>>,
+    mut director: ResMut<MusicDirector>,
+    mut instances: ResMut<Assets<AudioInstance>>,
 ) {
-    audio
-        .play(asset_server.load("audios/reward.wav"))
-        .with_volume(volume.get_val());
+    let single = |track: &Option<(&'static str, Handle<AudioSource>)>| {
+        track
+            .iter()
+            .map(|(path, music)| (path.to_string(), music.clone()))
+            .collect::<Vec<_>>()
+    };
+    let playlist = match state.get() {
+        GameState::Menu => single(&assets.menu_music),
+        GameState::Game => {
+            let playlist = level_playlist(level.as_deref(), &server);
+            if playlist.is_empty() {
+                single(&assets.game_music)
+            } else {
+                playlist
+            }
+        }
+        GameState::Loading | GameState::Splash => Vec::new(),
+    };
+    director.crossfade_to(playlist, &audio, &mut instances, &fades);
 }
 
-fn start_bgm(
-    asset_server: Res<AssetServer>,
-    audio: Res<AudioChannel<Menu>>,
-    volume: Res<Volume>,
-    mut mtus: ResMut<BGMInitialized>,
+// Moves on to the next track of the playlist once the current one is over
+fn advance_playlist(
+    fades: Res<MusicFades>,
+    audio: Res<AudioChannel<Music>>,
+    instances: Res<Assets<AudioInstance>>,
+    mut director: ResMut<MusicDirector>,
 ) {
-    if *mtus == BGMInitialized::No {
-        audio
-            .play(asset_server.load("audios/bip-bop.ogg"))
-            .with_volume(volume.get_val())
-            .looped();
-        audio.pause();
-        *mtus = BGMInitialized::Yes;
-    } else {
-        audio.set_volume(volume.get_val());
-        audio.resume();
+    let over = director.playing.as_ref().is_some_and(|(_, instance)| {
+        instances
+            .get(instance)
+            .is_some_and(|instance| instance.state() == PlaybackState::Stopped)
+    });
+    if over {
+        director.play_next(&audio, &fades);
     }
 }
 
-fn stop_bgm(audio: Res<AudioChannel<Menu>>) {
-    audio.pause();
+// Layers start silent along with the level, and are raised by the intensity
+fn start_layers(
+    assets: Res<GameAssets>,
+    pulse: Res<AudioChannel<PulseLayer>>,
+    drone: Res<AudioChannel<DroneLayer>>,
+    mut intensity: ResMut<Intensity>,
+) {
+    *intensity = Intensity(0.0);
+    pulse.set_volume(0.0);
+    drone.set_volume(0.0);
+    if let Some((_, layer)) = &assets.pulse_layer {
+        pulse.play(layer.clone()).looped();
+    }
+    if let Some((_, layer)) = &assets.drone_layer {
+        drone.play(layer.clone()).looped();
+    }
 }
 
-fn start_ingame(
-    asset_server: Res<AssetServer>,
-    audio: Res<AudioChannel<InGame>>,
-    volume: Res<Volume>,
-    mut mtus: ResMut<GMInitialized>,
+fn stop_layers(
+    fades: Res<MusicFades>,
+    pulse: Res<AudioChannel<PulseLayer>>,
+    drone: Res<AudioChannel<DroneLayer>>,
 ) {
-    if *mtus == GMInitialized::No {
-        audio
-            .play(asset_server.load("audios/ganxta.ogg"))
-            .with_volume(volume.get_val())
-            .looped();
-        audio.pause();
-        *mtus = GMInitialized::Yes;
-    } else {
-        audio.set_volume(volume.get_val());
-        audio.resume();
+    pulse.stop().fade_out(AudioTween::linear(fades.fade_out));
+    drone.stop().fade_out(AudioTween::linear(fades.fade_out));
+}
+
+// The intensity follows the closest zombie to the player
+fn measure_intensity(
+    time: Res<Time>,
+    player: Query<&Transform, With<PlayerFlag>>,
+    zombies: Query<&Transform, With<Zombie>>,
+    mut intensity: ResMut<Intensity>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let target = zombies
+        .iter()
+        .map(|zombie| threat(zombie.translation.distance(player.translation)))
+        .fold(0.0, f32::max);
+    let step = INTENSITY_RATE * time.delta_seconds();
+    let next = Intensity(intensity.0 + (target - intensity.0).clamp(-step, step));
+    intensity.set_if_neq(next);
+}
+
+// The pulse comes in as soon as zombies get close, the drone only once they are about to strike
+fn apply_layer_volumes(
+    intensity: Res<Intensity>,
+    mixer: Mixer,
+    pulse: Res<AudioChannel<PulseLayer>>,
+    drone: Res<AudioChannel<DroneLayer>>,
+) {
+    if !intensity.is_changed() && !mixer.is_changed() {
+        return;
     }
+    pulse.set_volume(mixer.music() * layer_gain(intensity.0, 0.0, 0.5));
+    drone.set_volume(mixer.music() * layer_gain(intensity.0, 0.5, 1.0));
 }
 
-fn stop_ingame(audio: Res<AudioChannel<InGame>>) {
-    audio.pause();
+fn mixer_changed(mixer: Mixer) -> bool {
+    mixer.is_changed()
+}
+
+// Sounds already playing follow the volume settings as they are changed. The volume of the `Sfx`
+// channel is left alone, as it would override the gain of every sound placed by `place_sounds`
+fn apply_volumes(
+    mixer: Mixer,
+    music: Res<AudioChannel<Music>>,
+    splash: Res<AudioChannel<Splash>>,
+    ui: Res<AudioChannel<UiSounds>>,
+) {
+    music.set_volume(mixer.music());
+    splash.set_volume(mixer.music());
+    ui.set_volume(mixer.ui());
+}
+
+fn play_sound<T: Resource>(
+    audio: &AudioChannel<T>,
+    sound: &Option<(&'static str, Handle<AudioSource>)>,
+    volume: f64,
+) {
+    if let Some((_, sound)) = sound {
+        audio.play(sound.clone()).with_volume(volume);
+    }
+}
+
+// Hits the player is immune to make no sound, and neither do the ones after the hit that ended
+// the run
+fn play_hit(
+    mut hit_by_enemy: EventReader<PlayerHitByEnemy>,
+    god_mode: Res<GodMode>,
+    dashes: Query<&Dash>,
+    pstatus: Res<PlayerStatus>,
+    assets: Res<GameAssets>,
+    audio: Res<AudioChannel<Sfx>>,
+    mixer: Mixer,
+) {
+    let in_run = *pstatus == PlayerStatus::Active || pstatus.is_changed();
+    if hit_by_enemy.read().count() > 0 && in_run && !is_immune(&god_mode, &dashes) {
+        play_sound(&audio, &assets.hit_sound, mixer.sfx());
+    }
+}
+
+fn play_reward(
+    mut reached_flag: EventReader<PlayerReachedFlag>,
+    assets: Res<GameAssets>,
+    audio: Res<AudioChannel<Sfx>>,
+    mixer: Mixer,
+) {
+    if reached_flag.read().count() > 0 {
+        play_sound(&audio, &assets.reward_sound, mixer.sfx());
+    }
+}
+
+// A step is heard whenever the walking animation of the player shows a foot on the ground
+fn play_footsteps(
+    query: Query<
+        (&TextureAtlasSprite, &Animation),
+        (With<PlayerFlag>, Changed<TextureAtlasSprite>),
+    >,
+    assets: Res<GameAssets>,
+    audio: Res<AudioChannel<Sfx>>,
+    mixer: Mixer,
+) {
+    for (sprite, animation) in &query {
+        let frame = animation
+            .sprites
+            .iter()
+            .position(|index| *index == sprite.index);
+        if frame.is_some_and(|frame| FOOTSTEP_FRAMES.contains(&frame)) {
+            play_sound(&audio, &assets.footstep_sound, mixer.sfx());
+        }
+    }
+}
+
+fn play_clicks(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+    assets: Res<GameAssets>,
+    audio: Res<AudioChannel<UiSounds>>,
+    mixer: Mixer,
+) {
+    if interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        play_sound(&audio, &assets.click_sound, mixer.ui());
+    }
+}
+
+// Zombies and spawners make themselves heard
+fn attach_emitters(
+    mut commands: Commands,
+    zombies: Query<Entity, Added<Zombie>>,
+    spawners: Query<Entity, Added<Spawner>>,
+) {
+    for zombie in &zombies {
+        commands
+            .entity(zombie)
+            .insert((Emitter::default(), Groan::random()));
+    }
+    for spawner in &spawners {
+        commands.entity(spawner).insert(Emitter::default());
+    }
+}
+
+// Starts `sound` from an emitter, already placed around the player so that it does not pop on
+// its first frame
+fn play_from(
+    audio: &AudioChannel<Sfx>,
+    sound: &Handle<AudioSource>,
+    mixer: &Mixer,
+    listener: Option<&Transform>,
+    (transform, emitter): (&Transform, &mut Emitter),
+) {
+    let (gain, panning) = listener.map_or((1.0, 0.5), |listener| {
+        spatial_gain(
+            listener.translation.truncate(),
+            transform.translation.truncate(),
+        )
+    });
+    let instance = audio
+        .play(sound.clone())
+        .with_volume(mixer.sfx() * gain)
+        .with_panning(panning)
+        .handle();
+    emitter.instances.push(instance);
+}
+
+fn play_groans(
+    time: Res<Time>,
+    assets: Res<GameAssets>,
+    audio: Res<AudioChannel<Sfx>>,
+    mixer: Mixer,
+    player: Query<&Transform, With<PlayerFlag>>,
+    mut zombies: Query<(&Transform, &mut Groan, &mut Emitter)>,
+) {
+    for (transform, mut groan, mut emitter) in &mut zombies {
+        if !groan.tick(time.delta()).finished() {
+            continue;
+        }
+        *groan = Groan::random();
+        if let Some((_, sound)) = &assets.zombie_groan {
+            let listener = player.get_single().ok();
+            play_from(&audio, sound, &mixer, listener, (transform, &mut emitter));
+        }
+    }
+}
+
+fn play_spawners(
+    mut activated: EventReader<SpawnerActivated>,
+    assets: Res<GameAssets>,
+    audio: Res<AudioChannel<Sfx>>,
+    mixer: Mixer,
+    player: Query<&Transform, With<PlayerFlag>>,
+    mut emitters: Query<(&Transform, &mut Emitter), With<Spawner>>,
+) {
+    for SpawnerActivated { spawner } in activated.read() {
+        let (Some((_, sound)), Ok((transform, mut emitter))) =
+            (&assets.spawner_sound, emitters.get_mut(*spawner))
+        else {
+            continue;
+        };
+        let listener = player.get_single().ok();
+        play_from(&audio, sound, &mixer, listener, (transform, &mut emitter));
+    }
+}
+
+// Sounds still playing follow their emitter and the player, on top of the SFX bus. The ones that
+// are over are forgotten
+fn place_sounds(
+    mixer: Mixer,
+    player: Query<&Transform, With<PlayerFlag>>,
+    mut emitters: Query<(&Transform, &mut Emitter)>,
+    mut instances: ResMut<Assets<AudioInstance>>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    for (transform, mut emitter) in &mut emitters {
+        let (gain, panning) = spatial_gain(
+            player.translation.truncate(),
+            transform.translation.truncate(),
+        );
+        emitter.instances.retain(|instance| {
+            let Some(instance) = instances.get_mut(instance) else {
+                return true;
+            };
+            if instance.state() == PlaybackState::Stopped {
+                return false;
+            }
+            instance.set_volume(mixer.sfx() * gain, AudioTween::default());
+            instance.set_panning(panning, AudioTween::default());
+            true
+        });
+    }
 }
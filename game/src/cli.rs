@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::{
+    environment::NextLevelSeed,
+    level::{CustomLevel, Level},
+    replay::{self, Replay},
+};
+
+pub const USAGE: &str = "\
+Usage: game [OPTIONS]
+
+Options:
+  --seed <SEED>    Play the level generated from SEED
+  --level <FILE>   Play the level described in FILE
+  --replay <FILE>  Watch the replay saved in FILE, on the level it was recorded on
+  --skip-splash    Start on the main menu
+  --windowed       Run in a window (default)
+  --fullscreen     Run in fullscreen
+  --mute           Start with the sound muted
+  --headless       Play the replay given with --replay without a window, then exit
+  --help           Print this message";
+
+// Options the game was launched with
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Args {
+    pub seed: Option<u64>,
+    pub level: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub skip_splash: bool,
+    pub fullscreen: bool,
+    pub mute: bool,
+    pub headless: bool,
+    pub help: bool,
+}
+
+impl Args {
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    // Reads the arguments following the program name. When both `--windowed` and `--fullscreen`
+    // are given, the last one wins. A headless run has nobody to play it, so it needs a replay
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let seed = args.next().ok_or("--seed expects a value")?;
+                    parsed.seed = Some(seed.parse().map_err(|_| format!("Not a seed: {seed}"))?);
+                }
+                "--level" => {
+                    let path = args.next().ok_or("--level expects a file")?;
+                    parsed.level = Some(PathBuf::from(path));
+                }
+                "--replay" => {
+                    let path = args.next().ok_or("--replay expects a file")?;
+                    parsed.replay = Some(PathBuf::from(path));
+                }
+                "--skip-splash" => parsed.skip_splash = true,
+                "--windowed" => parsed.fullscreen = false,
+                "--fullscreen" => parsed.fullscreen = true,
+                "--mute" => parsed.mute = true,
+                "--headless" => parsed.headless = true,
+                "--help" | "-h" => parsed.help = true,
+                _ => return Err(format!("Unknown argument: {arg}")),
+            }
+        }
+        if parsed.replay.is_some() && (parsed.seed.is_some() || parsed.level.is_some()) {
+            return Err(
+                "--replay plays the level it was recorded on, without --seed or --level".into(),
+            );
+        }
+        if parsed.headless && parsed.replay.is_none() {
+            return Err("--headless needs a replay to play, given with --replay".into());
+        }
+        Ok(parsed)
+    }
+
+    // Picking a level to play skips the menus altogether
+    pub fn starts_in_game(&self) -> bool {
+        self.seed.is_some() || self.level.is_some() || self.replay.is_some()
+    }
+
+    // Sets up the level to play or the replay to watch, shared by the windowed and headless game
+    pub fn apply_level(&self, app: &mut App) -> Result<(), String> {
+        if let Some(seed) = self.seed {
+            app.insert_resource(NextLevelSeed(Some(seed)));
+        }
+        if let Some(path) = &self.level {
+            let level = Level::load(path)
+                .map_err(|err| format!("Could not load level {}: {err}", path.display()))?;
+            app.insert_resource(CustomLevel(level));
+        }
+        if let Some(path) = &self.replay {
+            let replay = Replay::load(path)
+                .map_err(|err| format!("Could not load replay {}: {err}", path.display()))?;
+            app.add_systems(Startup, move |mut commands: Commands| {
+                replay::watch(&mut commands, replay.clone());
+            });
+        }
+        Ok(())
+    }
+}
@@ -6,10 +6,21 @@ pub const WINDOW_HEIGHT: f32 = 864.0;
 pub const WINDOW_BOTTOM_Y: f32 = WINDOW_HEIGHT / -2.0;
 pub const WINDOW_LEFT_X: f32 = WINDOW_WIDTH / -2.0;
 
-pub const FLOOR_THICKNESS: f32 = 1.0;
+pub const FLOOR_THICKNESS: f32 = 10.0;
 pub const COLOR_FLOOR: Color = Color::rgb(0.45, 0.55, 0.66);
 
 pub const PLAYER_VELOCITY_X: f32 = 150.0;
+// In pixels per second squared, the player gets to full speed in 0.15s and stops in 0.1s
+pub const PLAYER_ACCELERATION: f32 = 1000.0;
+pub const PLAYER_DECELERATION: f32 = 1500.0;
+
+// A dash covers 90 pixels, and can be done again a second after it started
+pub const DASH_SPEED: f32 = 600.0;
+pub const DASH_DURATION: f32 = 0.15;
+pub const DASH_COOLDOWN: f32 = 1.0;
+pub const DASH_INVULNERABILITY: f32 = 0.3;
+
+pub const PIXELS_PER_METER: f32 = 50.0;
 
 pub const SPRTPL_COLS: usize = 9;
 pub const SPRTPL_ROWS: usize = 4;
@@ -0,0 +1,327 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    input::InputSystem,
+    prelude::*,
+};
+use bevy_rapier2d::{prelude::*, render::DebugRenderContext};
+
+use crate::{
+    environment::{self, LevelSeed, NextLevelSeed, Zombie},
+    events::PlayerReachedFlag,
+    input,
+    loading::GameAssets,
+    player::{GodMode, RunCheated},
+    GameState, PlayerFlag, WinFlag, TEXT_COLOR,
+};
+
+// Toggles the collider rendering along with the FPS and entity counts
+pub const OVERLAY_KEY: KeyCode = KeyCode::F3;
+// Opens and closes the developer console
+pub const CONSOLE_KEY: KeyCode = KeyCode::Grave;
+
+// How many lines of output the console keeps on screen
+const CONSOLE_LINES: usize = 8;
+
+// Where `spawn zombie` places the zombie, relative to the player. It walks towards the player
+const ZOMBIE_OFFSET: Vec2 = Vec2::new(150.0, 0.0);
+const ZOMBIE_VELOCITY: Vec2 = Vec2::new(-20.0, 0.0);
+
+// A command typed into the console
+#[derive(Event, Debug, PartialEq, Clone, Copy)]
+pub enum ConsoleCommand {
+    Help,
+    SpawnZombie,
+    Teleport(Vec2),
+    GodMode,
+    // Shows the seed of the current level, or picks the one of the next level
+    Seed(Option<u64>),
+    Win,
+}
+
+impl ConsoleCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            ["help"] => Ok(Self::Help),
+            ["spawn", "zombie"] => Ok(Self::SpawnZombie),
+            ["teleport", x, y] => match (x.parse(), y.parse()) {
+                (Ok(x), Ok(y)) => Ok(Self::Teleport(Vec2::new(x, y))),
+                _ => Err(format!("Not a position: {x} {y}")),
+            },
+            ["godmode"] => Ok(Self::GodMode),
+            ["seed"] => Ok(Self::Seed(None)),
+            ["seed", seed] => seed
+                .parse()
+                .map(|seed| Self::Seed(Some(seed)))
+                .map_err(|_| format!("Not a seed: {seed}")),
+            ["win"] => Ok(Self::Win),
+            _ => Err(format!("Unknown command: {line}, try help")),
+        }
+    }
+}
+
+// Whether the debug overlay is shown
+#[derive(Resource, Debug, Default, PartialEq, Eq, Clone, Copy)]
+struct DebugOverlay(bool);
+
+// Line being typed and the last lines printed by the console
+#[derive(Resource, Debug, Default)]
+struct Console {
+    open: bool,
+    input: String,
+    output: Vec<String>,
+}
+
+impl Console {
+    fn print(&mut self, line: impl Into<String>) {
+        self.output.push(line.into());
+        if self.output.len() > CONSOLE_LINES {
+            self.output.remove(0);
+        }
+    }
+}
+
+// Tag component for the text of the debug overlay
+#[derive(Component)]
+struct DebugOverlayText;
+
+// Tag component for the console panel
+#[derive(Component)]
+struct ConsolePanel;
+
+// This plugin provides tools for developing the game: an overlay showing the colliders, the FPS
+// and how many entities are alive, and a console to cheat through a level
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+
+        app.init_resource::<DebugOverlay>()
+            .init_resource::<Console>()
+            .add_event::<ConsoleCommand>()
+            .add_systems(OnExit(GameState::Loading), spawn_debug_ui)
+            // The console grabs the keyboard before the game gets to read it
+            .add_systems(
+                PreUpdate,
+                console_keys.after(InputSystem).before(input::read_actions),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_overlay,
+                    update_overlay.run_if(resource_equals(DebugOverlay(true))),
+                    run_console_commands,
+                    update_console.run_if(resource_changed::<Console>()),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn spawn_debug_ui(mut commands: Commands, assets: Res<GameAssets>) {
+    let text_style = TextStyle {
+        font: assets.font.clone(),
+        font_size: 20.0,
+        color: TEXT_COLOR,
+    };
+
+    commands.spawn((
+        TextBundle::from_section("", text_style.clone()).with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        Visibility::Hidden,
+        DebugOverlayText,
+    ));
+
+    commands.spawn((
+        TextBundle::from_section("", text_style)
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(0.0),
+                left: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            })
+            .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.7)),
+        Visibility::Hidden,
+        ConsolePanel,
+    ));
+}
+
+fn toggle_overlay(
+    keyboard: Res<Input<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    debug_render: Option<ResMut<DebugRenderContext>>,
+    mut text: Query<&mut Visibility, With<DebugOverlayText>>,
+) {
+    if !keyboard.just_pressed(OVERLAY_KEY) {
+        return;
+    }
+
+    overlay.0 = !overlay.0;
+    if let Some(mut debug_render) = debug_render {
+        debug_render.enabled = overlay.0;
+    }
+    for mut visibility in &mut text {
+        *visibility = if overlay.0 {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn update_overlay(
+    diagnostics: Option<Res<DiagnosticsStore>>,
+    entities: Query<()>,
+    zombies: Query<(), With<Zombie>>,
+    colliders: Query<(), With<Collider>>,
+    mut text: Query<&mut Text, With<DebugOverlayText>>,
+) {
+    let fps = diagnostics
+        .as_ref()
+        .and_then(|diagnostics| diagnostics.get(FrameTimeDiagnosticsPlugin::FPS))
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+
+    for mut text in &mut text {
+        text.sections[0].value = format!(
+            "FPS {fps:.0}\nEntities {}\nColliders {}\nZombies {}",
+            entities.iter().count(),
+            colliders.iter().count(),
+            zombies.iter().count(),
+        );
+    }
+}
+
+// While the console is open every key goes to it, and the game sees the keyboard as idle
+fn console_keys(
+    mut keyboard: ResMut<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut console: ResMut<Console>,
+    mut requests: EventWriter<ConsoleCommand>,
+) {
+    if !console.open {
+        characters.clear();
+        if keyboard.just_pressed(CONSOLE_KEY) {
+            console.open = true;
+            keyboard.reset_all();
+        }
+        return;
+    }
+
+    if keyboard.any_just_pressed([CONSOLE_KEY, KeyCode::Escape]) {
+        console.open = false;
+    } else if keyboard.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        if !line.trim().is_empty() {
+            console.print(format!("> {line}"));
+            match ConsoleCommand::parse(&line) {
+                Ok(request) => requests.send(request),
+                Err(message) => console.print(message),
+            }
+        }
+    } else if keyboard.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+
+    for character in characters.read() {
+        if console.open && !character.char.is_control() && character.char != '`' {
+            console.input.push(character.char);
+        }
+    }
+    keyboard.reset_all();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_console_commands(
+    mut requests: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    game_state: Res<State<GameState>>,
+    mut god_mode: ResMut<GodMode>,
+    mut cheated: ResMut<RunCheated>,
+    seed: Res<LevelSeed>,
+    mut next_seed: ResMut<NextLevelSeed>,
+    mut players: Query<&mut Transform, With<PlayerFlag>>,
+    flags: Query<Entity, With<WinFlag>>,
+    mut reached_flag: EventWriter<PlayerReachedFlag>,
+) {
+    // Commands that only print something don't count as cheating
+    for request in requests.read() {
+        let in_game = *game_state.get() == GameState::Game;
+        match *request {
+            ConsoleCommand::Help => {
+                console.print("help, spawn zombie, teleport <x> <y>, godmode, seed [<seed>], win")
+            }
+            ConsoleCommand::GodMode => {
+                god_mode.0 = !god_mode.0;
+                cheated.0 = true;
+                console.print(if god_mode.0 {
+                    "God mode on"
+                } else {
+                    "God mode off"
+                });
+            }
+            ConsoleCommand::Seed(None) => console.print(format!("Level seed {}", seed.0)),
+            ConsoleCommand::Seed(Some(seed)) => {
+                next_seed.0 = Some(seed);
+                cheated.0 = true;
+                console.print(format!("Next level uses seed {seed}"));
+            }
+            _ if !in_game => console.print("Only available during a game"),
+            ConsoleCommand::SpawnZombie => {
+                let Ok(player) = players.get_single() else {
+                    continue;
+                };
+                cheated.0 = true;
+                environment::spawn_zombie(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    player.translation.truncate() + ZOMBIE_OFFSET,
+                    ZOMBIE_VELOCITY,
+                );
+            }
+            ConsoleCommand::Teleport(position) => {
+                cheated.0 = true;
+                // The physics treats a `Transform` changed from outside as a teleport
+                for mut player in &mut players {
+                    player.translation = position.extend(player.translation.z);
+                }
+            }
+            ConsoleCommand::Win => {
+                cheated.0 = true;
+                for flag in &flags {
+                    reached_flag.send(PlayerReachedFlag { flag });
+                }
+            }
+        }
+    }
+}
+
+fn update_console(
+    console: Res<Console>,
+    mut panel: Query<(&mut Text, &mut Visibility), With<ConsolePanel>>,
+) {
+    for (mut text, mut visibility) in &mut panel {
+        *visibility = if console.open {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+
+        let mut lines = console.output.clone();
+        lines.push(format!("> {}_", console.input));
+        text.sections[0].value = lines.join("\n");
+    }
+}
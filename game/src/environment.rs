@@ -1,17 +1,20 @@
-use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle, time::Stopwatch};
 use bevy_rapier2d::prelude::*;
 
 use crate::consts;
-
-use rand::Rng;
+use crate::events::{PickupCollected, SpawnerActivated};
+use crate::level::{BlockKind, CustomLevel, Level};
+use crate::loading::GameAssets;
+use crate::logging::{AI, LEVEL};
+use crate::physics::{GameplaySet, Layer, PhysicsInterpolation};
 
 #[derive(Component)]
-struct Zombie;
+pub struct Zombie;
 
 #[derive(Component)]
-struct Spawner;
+pub struct Spawner;
 
-use super::{despawn_screen, GameState, OnGameScreen, WinFlag};
+use crate::{despawn_screen, GameState, OnGameScreen, WinFlag};
 
 #[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
 enum SpawnStatus {
@@ -19,14 +22,42 @@ enum SpawnStatus {
     Standby,
 }
 
+// Seed the current level was generated from
+#[derive(Resource, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LevelSeed(pub u64);
+
+// Seed to use for the next level, a random one is picked when it is not set
+#[derive(Resource, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct NextLevelSeed(pub Option<u64>);
+
+// Time spent in the current level, zombies are spawned and despawned based on it
+#[derive(Resource, Default, Deref, DerefMut)]
+struct ZombieClock(Stopwatch);
+
 pub struct PlatformsPlugin;
 
 impl Plugin for PlatformsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Game), setup)
-            .insert_resource(SpawnStatus::Ready)
-            .add_systems(Update, spawn_zombies.run_if(in_state(GameState::Game)))
-            .add_systems(Update, despawn_zombies.run_if(in_state(GameState::Game)))
+        app.insert_resource(SpawnStatus::Ready)
+            .insert_resource(LevelSeed(0))
+            .init_resource::<NextLevelSeed>()
+            .init_resource::<ZombieClock>()
+            .add_systems(
+                OnEnter(GameState::Game),
+                (roll_seed, setup, reset_zombies).chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    tick_zombie_clock,
+                    spawn_zombies,
+                    despawn_zombies,
+                    remove_collected_pickups,
+                )
+                    .chain()
+                    .in_set(GameplaySet::Logic)
+                    .run_if(in_state(GameState::Game)),
+            )
             .add_systems(OnExit(GameState::Game), despawn_screen::<Zombie>)
             .add_systems(OnExit(GameState::Game), despawn_screen::<OnGameScreen>);
     }
@@ -37,10 +68,13 @@ struct PlatformBundle {
     sprite_bundle: SpriteBundle,
     body: RigidBody,
     collider: Collider,
+    collision_groups: CollisionGroups,
+    solver_groups: SolverGroups,
 }
 
 impl PlatformBundle {
     fn new(translation: Vec3, scale: Vec3, collider: Collider) -> (Self, OnGameScreen) {
+        let (collision_groups, solver_groups) = Layer::Terrain.groups();
         (
             Self {
                 sprite_bundle: SpriteBundle {
@@ -57,84 +91,51 @@ impl PlatformBundle {
                 },
                 body: RigidBody::Fixed,
                 collider,
+                collision_groups,
+                solver_groups,
             },
             OnGameScreen,
         )
     }
 }
 
-fn overlaps(fst: (f32, f32), snd: (f32, f32)) -> bool {
-    (fst.0 - 30. < snd.0 && snd.0 < fst.0 + 30.) || (fst.1 - 30. < snd.1 && snd.1 < fst.1 + 30.)
-}
-
-fn is_invalid_spot(spots: Vec<(f32, f32)>, new_spot: (f32, f32)) -> bool {
-    for spot in spots {
-        if overlaps(spot, new_spot) {
-            return true;
-        }
-    }
-    false
+fn roll_seed(mut seed: ResMut<LevelSeed>, mut next_seed: ResMut<NextLevelSeed>) {
+    seed.0 = next_seed.0.take().unwrap_or_else(rand::random);
+    info!(target: LEVEL, "Generating level with seed {}", seed.0);
 }
 
 pub fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    seed: Res<LevelSeed>,
+    custom_level: Option<Res<CustomLevel>>,
 ) {
-    let mut spots: Vec<(f32, f32)> = vec![
-        //flag
-        (
-            consts::WINDOW_LEFT_X + 1480.0,
-            consts::WINDOW_BOTTOM_Y + 800.0,
-        ),
-        //player
-        (
-            consts::WINDOW_LEFT_X + 730.0,
-            consts::WINDOW_BOTTOM_Y + 50.0,
-        ),
-    ];
-    let mut rng = rand::thread_rng();
-    for _c in 1..=60 {
-        let mut x: f32 =
-            rng.gen_range((consts::WINDOW_LEFT_X + 25.)..=(consts::WINDOW_WIDTH / 2. - 25.));
-        let mut y: f32 =
-            rng.gen_range((consts::WINDOW_BOTTOM_Y + 25.)..=(consts::WINDOW_HEIGHT / 2. - 25.));
-        let is_spawner: i32 = rng.gen_range(0..=1);
-
-        let mut i = 0;
-        while is_invalid_spot(spots.clone(), (x, y)) {
-            x = rng.gen_range((consts::WINDOW_LEFT_X + 25.)..=(consts::WINDOW_WIDTH / 2. - 25.));
-            y = rng.gen_range((consts::WINDOW_BOTTOM_Y + 25.)..=(consts::WINDOW_HEIGHT / 2. - 25.));
-            i += 1;
-            if i > 100 {
-                break;
-            }
-        }
-
-        if i > 100 {
-            continue;
+    let level = match custom_level {
+        Some(custom_level) => {
+            info!(target: LEVEL, "Playing a custom level");
+            custom_level.0.clone()
         }
+        None => Level::generate(seed.0),
+    };
 
-        if is_spawner == 1 {
-            spots.push((x, y));
-        }
-        // println!("Accept {}", c);
-
+    for block in &level.blocks {
         // Cube
         let cube_template = MaterialMesh2dBundle {
             mesh: meshes.add(shape::Cube::new(50.).into()).into(),
             material: materials.add(ColorMaterial::from(Color::TURQUOISE)),
-            transform: Transform::from_translation(Vec3::new(x, y, 0.)),
+            transform: Transform::from_translation(block.position.extend(0.)),
             ..default()
         };
 
-        if is_spawner == 0 {
+        if block.kind == BlockKind::Obstacle {
             commands.spawn((
                 cube_template,
                 OnGameScreen,
                 RigidBody::Fixed,
                 Collider::cuboid(25., 25.),
+                Layer::Terrain.groups(),
             ));
         } else {
             commands.spawn((
@@ -143,6 +144,7 @@ pub fn setup(
                 OnGameScreen,
                 RigidBody::Fixed,
                 Collider::cuboid(25., 25.),
+                Layer::Terrain.groups(),
             ));
         }
     }
@@ -191,7 +193,6 @@ pub fn setup(
         Collider::cuboid(0.5, 0.5),
     ));
 
-    let flag: bevy::prelude::Handle<Image> = asset_server.load("texture/flag.png");
     commands
         .spawn((
             SpriteBundle {
@@ -200,129 +201,117 @@ pub fn setup(
                     ..Default::default()
                 },
                 transform: Transform {
-                    translation: Vec3::new(
-                        consts::WINDOW_LEFT_X + 1480.0,
-                        consts::WINDOW_BOTTOM_Y + 800.0,
-                        0.0,
-                    ),
+                    translation: level.flag.extend(0.0),
                     scale: Vec3::new(0.2, 0.2, 1.0),
                     ..Default::default()
                 },
-                texture: flag,
+                texture: assets.flag_texture.clone(),
                 ..Default::default()
             },
             OnGameScreen,
             WinFlag,
             Collider::cuboid(124., 124.),
+            Layer::Trigger.groups(),
         ))
         .insert(Sensor)
-        .insert(RigidBody::Dynamic);
+        .insert(RigidBody::Fixed);
+}
+
+fn reset_zombies(mut clock: ResMut<ZombieClock>, mut sstatus: ResMut<SpawnStatus>) {
+    clock.reset();
+    *sstatus = SpawnStatus::Ready;
+}
+
+fn tick_zombie_clock(time: Res<Time>, mut clock: ResMut<ZombieClock>) {
+    clock.tick(time.delta());
 }
 
 fn spawn_zombies(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    time: Res<Time>,
-    mut query: Query<&mut Transform, With<Spawner>>,
+    clock: Res<ZombieClock>,
+    mut query: Query<(Entity, &mut Transform), With<Spawner>>,
     mut sstatus: ResMut<SpawnStatus>,
+    mut activated: EventWriter<SpawnerActivated>,
 ) {
-    if time.elapsed_seconds().round() as i32 % 2 == 0
-        && time.elapsed_seconds() > 1.
+    if clock.elapsed_secs().round() as i32 % 2 == 0
+        && clock.elapsed_secs() > 1.
         && *sstatus == SpawnStatus::Ready
     {
         *sstatus = SpawnStatus::Standby;
-        for transform in &mut query {
-            commands.spawn((
-                MaterialMesh2dBundle {
-                    mesh: meshes.add(shape::Cube::new(25.).into()).into(),
-                    material: materials.add(ColorMaterial::from(Color::RED)),
-                    transform: Transform::from_translation(Vec3::new(
-                        transform.translation.x + 37.5,
-                        transform.translation.y,
-                        0.,
-                    )),
-                    ..default()
-                },
-                Zombie,
-                RigidBody::KinematicVelocityBased,
-                Collider::cuboid(12.5, 12.5),
-                Velocity {
-                    linvel: Vec2::new(20.0, 0.0),
-                    angvel: 0.,
-                },
-            ));
-            commands.spawn((
-                MaterialMesh2dBundle {
-                    mesh: meshes.add(shape::Cube::new(25.).into()).into(),
-                    material: materials.add(ColorMaterial::from(Color::RED)),
-                    transform: Transform::from_translation(Vec3::new(
-                        transform.translation.x - 37.5,
-                        transform.translation.y,
-                        0.,
-                    )),
-                    ..default()
-                },
-                Zombie,
-                RigidBody::KinematicVelocityBased,
-                Collider::cuboid(12.5, 12.5),
-                Velocity {
-                    linvel: Vec2::new(-20.0, 0.0),
-                    angvel: 0.,
-                },
-            ));
-            commands.spawn((
-                MaterialMesh2dBundle {
-                    mesh: meshes.add(shape::Cube::new(25.).into()).into(),
-                    material: materials.add(ColorMaterial::from(Color::RED)),
-                    transform: Transform::from_translation(Vec3::new(
-                        transform.translation.x,
-                        transform.translation.y + 37.5,
-                        0.,
-                    )),
-                    ..default()
-                },
-                Zombie,
-                RigidBody::KinematicVelocityBased,
-                Collider::cuboid(12.5, 12.5),
-                Velocity {
-                    linvel: Vec2::new(0.0, 20.0),
-                    angvel: 0.,
-                },
-            ));
-            commands.spawn((
-                MaterialMesh2dBundle {
-                    mesh: meshes.add(shape::Cube::new(25.).into()).into(),
-                    material: materials.add(ColorMaterial::from(Color::RED)),
-                    transform: Transform::from_translation(Vec3::new(
-                        transform.translation.x,
-                        transform.translation.y - 37.5,
-                        0.,
-                    )),
-                    ..default()
-                },
-                Zombie,
-                RigidBody::KinematicVelocityBased,
-                Collider::cuboid(12.5, 12.5),
-                Velocity {
-                    linvel: Vec2::new(0.0, -20.0),
-                    angvel: 0.,
-                },
-            ));
+        debug!(
+            target: AI,
+            "Spawning zombies from {} spawners",
+            query.iter().len()
+        );
+        for (spawner, transform) in &mut query {
+            activated.send(SpawnerActivated { spawner });
+            // One zombie leaves the spawner on each of its sides
+            for direction in [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y] {
+                spawn_zombie(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    transform.translation.truncate() + direction * 37.5,
+                    direction * 20.0,
+                );
+            }
         }
     }
 }
 
+// Spawns a single zombie walking at a constant velocity, in pixels per second
+pub fn spawn_zombie(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    position: Vec2,
+    velocity: Vec2,
+) -> Entity {
+    commands
+        .spawn((
+            MaterialMesh2dBundle {
+                mesh: meshes.add(shape::Cube::new(25.).into()).into(),
+                material: materials.add(ColorMaterial::from(Color::RED)),
+                transform: Transform::from_translation(position.extend(0.)),
+                ..default()
+            },
+            Zombie,
+            PhysicsInterpolation::default(),
+            RigidBody::KinematicVelocityBased,
+            Collider::cuboid(12.5, 12.5),
+            Layer::Enemy.groups(),
+            Velocity {
+                linvel: velocity,
+                angvel: 0.,
+            },
+        ))
+        .id()
+}
+
 fn despawn_zombies(
     mut commands: Commands,
-    time: Res<Time>,
+    clock: Res<ZombieClock>,
     mut entities: Query<Entity, With<Zombie>>,
     mut sstatus: ResMut<SpawnStatus>,
 ) {
-    if time.elapsed_seconds().round() as i32 % 3 == 0 && time.elapsed_seconds() > 1. {
+    if clock.elapsed_secs().round() as i32 % 3 == 0 && clock.elapsed_secs() > 1. {
         *sstatus = SpawnStatus::Ready;
+        if !entities.is_empty() {
+            debug!(target: AI, "Despawning {} zombies", entities.iter().len());
+        }
         for entity in &mut entities {
             commands.entity(entity).despawn();
         }
     }
 }
+
+fn remove_collected_pickups(mut commands: Commands, mut collected: EventReader<PickupCollected>) {
+    for event in collected.read() {
+        debug!(target: LEVEL, "Removing collected pickup {:?}", event.pickup);
+        if let Some(mut pickup) = commands.get_entity(event.pickup) {
+            pickup.despawn();
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    environment::Zombie, logging::PHYSICS, physics::GameplaySet, GameState, Pickup, PlayerFlag,
+    WinFlag,
+};
+
+// The player touched the flag at the end of the level
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerReachedFlag {
+    pub flag: Entity,
+}
+
+// A zombie ran into the player
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerHitByEnemy {
+    pub enemy: Entity,
+}
+
+// The player walked over a pickup
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PickupCollected {
+    pub pickup: Entity,
+}
+
+// A spawner let out a wave of zombies
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpawnerActivated {
+    pub spawner: Entity,
+}
+
+// This plugin turns the raw collisions reported by Rapier into gameplay events. Gameplay, audio
+// and UI systems only listen to the events they care about, and run after the routing so that
+// they react on the same step
+pub struct GameEventsPlugin;
+
+impl Plugin for GameEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerReachedFlag>()
+            .add_event::<PlayerHitByEnemy>()
+            .add_event::<PickupCollected>()
+            .add_event::<SpawnerActivated>()
+            .add_systems(
+                FixedUpdate,
+                route_collisions
+                    .in_set(GameplaySet::Events)
+                    .run_if(in_state(GameState::Game)),
+            );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn route_collisions(
+    mut collisions: EventReader<CollisionEvent>,
+    players: Query<(), With<PlayerFlag>>,
+    flags: Query<(), With<WinFlag>>,
+    zombies: Query<(), With<Zombie>>,
+    pickups: Query<(), With<Pickup>>,
+    mut reached_flag: EventWriter<PlayerReachedFlag>,
+    mut hit_by_enemy: EventWriter<PlayerHitByEnemy>,
+    mut pickup_collected: EventWriter<PickupCollected>,
+) {
+    for collision in collisions.read() {
+        let (entity1, entity2) = match *collision {
+            CollisionEvent::Started(entity1, entity2, _) => {
+                debug!(target: PHYSICS, "Contact started between {entity1:?} and {entity2:?}");
+                (entity1, entity2)
+            }
+            CollisionEvent::Stopped(entity1, entity2, _) => {
+                debug!(target: PHYSICS, "Contact stopped between {entity1:?} and {entity2:?}");
+                continue;
+            }
+        };
+        // Only contacts involving the player mean something to the game
+        let other = if players.contains(entity1) {
+            entity2
+        } else if players.contains(entity2) {
+            entity1
+        } else {
+            continue;
+        };
+
+        if flags.contains(other) {
+            info!(target: PHYSICS, "Player reached the flag {other:?}");
+            reached_flag.send(PlayerReachedFlag { flag: other });
+        } else if zombies.contains(other) {
+            info!(target: PHYSICS, "Player hit by zombie {other:?}");
+            hit_by_enemy.send(PlayerHitByEnemy { enemy: other });
+        } else if pickups.contains(other) {
+            info!(target: PHYSICS, "Player collected pickup {other:?}");
+            pickup_collected.send(PickupCollected { pickup: other });
+        }
+    }
+}
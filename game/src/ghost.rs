@@ -0,0 +1,322 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{prelude::*, time::Stopwatch};
+use bevy_rapier2d::prelude::PhysicsSet;
+
+use crate::{
+    animator::Animation,
+    environment::{self, LevelSeed},
+    events::PlayerReachedFlag,
+    loading::GameAssets,
+    logging::{LEVEL, REPLAY},
+    physics::GameplaySet,
+    player::{
+        self, PlayerStatus, RunCheated, CYCLE_DELAY, P_IDLE, P_WALK_D, P_WALK_L, P_WALK_R, P_WALK_U,
+    },
+    GameState, OnGameScreen, PlayerFlag,
+};
+
+// Folder holding the best run for every seed that was won at least once
+pub const GHOST_DIR: &str = "replays/ghosts";
+
+const GHOST_HEADER: &str = "passive-power-ghost 1";
+
+// How see-through the ghost is drawn
+const GHOST_ALPHA: f32 = 0.4;
+
+// Position of the player at a given time of a run
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct GhostSample {
+    pub time: f32,
+    pub position: Vec2,
+}
+
+// Path followed by the player during a won run
+#[derive(Resource, Debug, Default, PartialEq, Clone)]
+pub struct GhostRun {
+    pub seed: u64,
+    pub samples: Vec<GhostSample>,
+}
+
+impl GhostRun {
+    pub fn path_for(seed: u64) -> PathBuf {
+        Path::new(GHOST_DIR).join(format!("{seed}.ghost"))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_string())
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut lines = text.lines();
+        if lines.next() != Some(GHOST_HEADER) {
+            return Err(invalid("not a ghost file"));
+        }
+        let seed = lines
+            .next()
+            .and_then(|line| line.strip_prefix("seed "))
+            .and_then(|seed| seed.parse().ok())
+            .ok_or_else(|| invalid("missing seed"))?;
+
+        let mut samples = Vec::new();
+        for line in lines.filter(|line| !line.is_empty()) {
+            let values = line
+                .split(' ')
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid("bad sample"))?;
+            let [time, x, y] = values[..] else {
+                return Err(invalid("malformed sample"));
+            };
+            samples.push(GhostSample {
+                time,
+                position: Vec2::new(x, y),
+            });
+        }
+
+        Ok(Self { seed, samples })
+    }
+
+    // Time it took to reach the flag
+    pub fn duration(&self) -> f32 {
+        self.samples.last().map_or(0., |sample| sample.time)
+    }
+
+    // Where the ghost is at `time`, interpolated between the two closest samples
+    pub fn position_at(&self, time: f32) -> Option<Vec2> {
+        let next = self.samples.iter().position(|sample| sample.time >= time);
+        match next {
+            Some(0) => self.samples.first().map(|sample| sample.position),
+            Some(i) => {
+                let (from, to) = (self.samples[i - 1], self.samples[i]);
+                let span = to.time - from.time;
+                let t = if span > 0. {
+                    (time - from.time) / span
+                } else {
+                    1.
+                };
+                Some(from.position.lerp(to.position, t))
+            }
+            None => self.samples.last().map(|sample| sample.position),
+        }
+    }
+}
+
+impl std::fmt::Display for GhostRun {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{GHOST_HEADER}")?;
+        writeln!(f, "seed {}", self.seed)?;
+        for sample in &self.samples {
+            writeln!(
+                f,
+                "{} {} {}",
+                sample.time, sample.position.x, sample.position.y
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Tag component for the translucent sprite replaying the best run
+#[derive(Component)]
+struct Ghost;
+
+// Walking animation the ghost is currently playing, if any
+#[derive(Component, Default)]
+struct GhostFacing(Option<&'static [usize]>);
+
+// Positions of the player recorded so far in the current run
+#[derive(Resource, Default)]
+struct CurrentRun {
+    clock: Stopwatch,
+    samples: Vec<GhostSample>,
+}
+
+// This plugin records the path of every run and lets the player race against the fastest run
+// previously won on the same seed
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentRun>()
+            .add_systems(
+                OnEnter(GameState::Game),
+                (start_run, spawn_ghost).after(environment::setup),
+            )
+            .add_systems(
+                FixedUpdate,
+                record_position
+                    .after(PhysicsSet::Writeback)
+                    .run_if(in_state(GameState::Game)),
+            )
+            .add_systems(
+                FixedUpdate,
+                save_best_run
+                    .in_set(GameplaySet::Logic)
+                    .run_if(in_state(GameState::Game))
+                    .run_if(resource_equals(RunCheated(false))),
+            )
+            .add_systems(Update, move_ghost.run_if(in_state(GameState::Game)));
+    }
+}
+
+fn start_run(mut run: ResMut<CurrentRun>) {
+    run.clock.reset();
+    run.samples.clear();
+}
+
+fn spawn_ghost(
+    mut commands: Commands,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+    assets: Res<GameAssets>,
+    seed: Res<LevelSeed>,
+) {
+    commands.remove_resource::<GhostRun>();
+    let Ok(best) = GhostRun::load(GhostRun::path_for(seed.0)) else {
+        return;
+    };
+    let Some(start) = best.position_at(0.) else {
+        return;
+    };
+
+    commands.spawn((
+        SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                index: P_IDLE,
+                color: Color::rgba(1.0, 1.0, 1.0, GHOST_ALPHA),
+                ..default()
+            },
+            texture_atlas: player::player_atlas(&mut atlases, &assets),
+            transform: Transform::from_translation(start.extend(0.0)),
+            ..default()
+        },
+        Ghost,
+        GhostFacing::default(),
+        OnGameScreen,
+    ));
+    commands.insert_resource(best);
+}
+
+fn record_position(
+    time: Res<Time>,
+    pstatus: Res<PlayerStatus>,
+    mut run: ResMut<CurrentRun>,
+    query: Query<&Transform, With<PlayerFlag>>,
+) {
+    if *pstatus != PlayerStatus::Active {
+        return;
+    }
+    let Ok(transform) = query.get_single() else {
+        return;
+    };
+
+    run.clock.tick(time.delta());
+    let sample = GhostSample {
+        time: run.clock.elapsed_secs(),
+        position: transform.translation.truncate(),
+    };
+    run.samples.push(sample);
+}
+
+// Only runs that reach the flag without cheating are compared against the best one
+fn save_best_run(
+    mut reached_flag: EventReader<PlayerReachedFlag>,
+    mut run: ResMut<CurrentRun>,
+    seed: Res<LevelSeed>,
+) {
+    if reached_flag.read().count() == 0 || run.samples.is_empty() {
+        return;
+    }
+
+    let current = GhostRun {
+        seed: seed.0,
+        samples: std::mem::take(&mut run.samples),
+    };
+    let path = GhostRun::path_for(seed.0);
+    let is_best = match GhostRun::load(&path) {
+        Ok(best) => current.duration() < best.duration(),
+        Err(_) => true,
+    };
+    if is_best {
+        info!(
+            target: LEVEL,
+            "New best time on seed {}: {:.2}s",
+            seed.0,
+            current.duration()
+        );
+        if let Err(err) = current.save(&path) {
+            error!(target: REPLAY, "Could not save ghost to {}: {err}", path.display());
+        }
+    }
+}
+
+fn move_ghost(
+    mut commands: Commands,
+    fixed_time: Res<Time<Fixed>>,
+    run: Res<CurrentRun>,
+    best: Option<Res<GhostRun>>,
+    mut query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut TextureAtlasSprite,
+            &mut GhostFacing,
+        ),
+        With<Ghost>,
+    >,
+) {
+    let Some(best) = best else {
+        return;
+    };
+    let Ok((ghost, mut transform, mut sprite, mut facing)) = query.get_single_mut() else {
+        return;
+    };
+    // Lag one step behind, the same way the player is drawn between its two last positions
+    let time = run.clock.elapsed_secs() - fixed_time.timestep().as_secs_f32()
+        + fixed_time.overstep().as_secs_f32();
+    let Some(position) = best.position_at(time) else {
+        return;
+    };
+
+    // Pick the walking animation the same way the player does, from the direction it moved in
+    let step = position - transform.translation.truncate();
+    let walk = if step.x > 0.0 {
+        Some(P_WALK_R)
+    } else if step.x < 0.0 {
+        Some(P_WALK_L)
+    } else if step.y > 0.0 {
+        Some(P_WALK_U)
+    } else if step.y < 0.0 {
+        Some(P_WALK_D)
+    } else {
+        None
+    };
+    transform.translation = position.extend(transform.translation.z);
+
+    if facing.0 != walk {
+        facing.0 = walk;
+        match walk {
+            Some(sprites) => {
+                commands
+                    .entity(ghost)
+                    .insert(Animation::new(sprites, CYCLE_DELAY));
+            }
+            None => {
+                commands.entity(ghost).remove::<Animation>();
+                sprite.index = P_IDLE;
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use bevy::{
+    app::AppExit, hierarchy::HierarchyPlugin, input::InputPlugin, prelude::*,
+    time::TimeUpdateStrategy, transform::TransformPlugin,
+};
+
+use crate::{environment, events, loading::GameAssets, logging::LEVEL, physics, player, GameState};
+
+// Length of a single simulated frame, every call to `App::update` runs exactly one fixed step
+pub const HEADLESS_TIMESTEP: Duration = physics::FIXED_TIMESTEP;
+
+// Game time after which a headless run is given up, in case the player never wins nor loses
+pub const HEADLESS_TIME_LIMIT: Duration = Duration::from_secs(300);
+
+// This plugin runs the gameplay without a window or audio. The game starts right away without
+// going through `GameState::Loading`, so every asset handle is a stub that is never loaded
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MinimalPlugins)
+            .add_plugins((
+                AssetPlugin::default(),
+                TransformPlugin,
+                HierarchyPlugin,
+                InputPlugin,
+            ))
+            .init_asset::<Image>()
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .init_asset::<TextureAtlas>()
+            .init_asset::<Font>()
+            .init_resource::<GameAssets>()
+            // Time moves forward by a fixed amount each frame regardless of the wall clock
+            .insert_resource(TimeUpdateStrategy::ManualDuration(HEADLESS_TIMESTEP))
+            .add_plugins(physics::GamePhysicsPlugin)
+            .add_state::<GameState>()
+            .add_plugins(events::GameEventsPlugin)
+            .add_plugins(environment::PlatformsPlugin)
+            .add_plugins(player::PlayerPlugin);
+    }
+}
+
+// Builds a headless app that is already in `GameState::Game`, with the level and player spawned
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(HeadlessPlugin);
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Game);
+    app.update();
+    app
+}
+
+// Quits once the run is over, which is when the game leaves `GameState::Game`
+pub fn exit_after_run(
+    game_state: Res<State<GameState>>,
+    time: Res<Time>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if *game_state.get() != GameState::Game {
+        exit.send(AppExit);
+    } else if time.elapsed() > HEADLESS_TIME_LIMIT {
+        warn!(target: LEVEL, "Headless run stopped after {:?}", HEADLESS_TIME_LIMIT);
+        exit.send(AppExit);
+    }
+}
@@ -0,0 +1,495 @@
+use bevy::{
+    ecs::system::SystemParam,
+    input::{
+        gamepad::{GamepadConnection, GamepadConnectionEvent},
+        InputSystem,
+    },
+    prelude::*,
+    reflect::{DynamicEnum, DynamicVariant},
+    utils::HashMap,
+};
+
+use crate::{
+    loading::GameAssets, logging::INPUT, physics::GameplaySet, GameState, OnGameScreen, TEXT_COLOR,
+};
+
+// How far the left stick has to be tilted before the player moves, out of 1
+pub const STICK_DEADZONE: f32 = 0.2;
+
+// Each axis of the stick is rounded to this many steps out of 1, so that replays store the exact
+// tilt the player moved with
+pub const STICK_STEPS: f32 = 100.0;
+
+// Everything the player can do, whatever key or gamepad button it is bound to
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Pause,
+    Restart,
+    Dash,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Pause,
+        Action::Restart,
+        Action::Dash,
+    ];
+
+    // Name of the action in the settings file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveUp => "move-up",
+            Action::MoveDown => "move-down",
+            Action::MoveLeft => "move-left",
+            Action::MoveRight => "move-right",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::Dash => "dash",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    // Name of the action on the controls screen
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Pause => "Pause",
+            Action::Restart => "Restart",
+            Action::Dash => "Dash",
+        }
+    }
+}
+
+// Key and gamepad button each action is bound to. A key or a button is bound to a single action
+// at most
+#[derive(Resource, Debug, PartialEq, Eq, Clone)]
+pub struct Bindings {
+    keys: HashMap<Action, KeyCode>,
+    buttons: HashMap<Action, GamepadButtonType>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let keys = [
+            (Action::MoveUp, KeyCode::W),
+            (Action::MoveDown, KeyCode::S),
+            (Action::MoveLeft, KeyCode::A),
+            (Action::MoveRight, KeyCode::D),
+            (Action::Pause, KeyCode::Escape),
+            (Action::Restart, KeyCode::R),
+            (Action::Dash, KeyCode::Space),
+        ];
+        let buttons = [
+            (Action::MoveUp, GamepadButtonType::DPadUp),
+            (Action::MoveDown, GamepadButtonType::DPadDown),
+            (Action::MoveLeft, GamepadButtonType::DPadLeft),
+            (Action::MoveRight, GamepadButtonType::DPadRight),
+            (Action::Pause, GamepadButtonType::Start),
+            (Action::Restart, GamepadButtonType::Select),
+            (Action::Dash, GamepadButtonType::South),
+        ];
+        Self {
+            keys: keys.into_iter().collect(),
+            buttons: buttons.into_iter().collect(),
+        }
+    }
+}
+
+impl Bindings {
+    pub fn key(&self, action: Action) -> Option<KeyCode> {
+        self.keys.get(&action).copied()
+    }
+
+    pub fn button(&self, action: Action) -> Option<GamepadButtonType> {
+        self.buttons.get(&action).copied()
+    }
+
+    // Binds `key` to `action`. The action it was bound to, if any, takes the previous key of
+    // `action` instead
+    pub fn bind_key(&mut self, action: Action, key: KeyCode) {
+        swap_binding(&mut self.keys, action, key);
+    }
+
+    pub fn bind_button(&mut self, action: Action, button: GamepadButtonType) {
+        swap_binding(&mut self.buttons, action, button);
+    }
+}
+
+fn swap_binding<T: PartialEq + Copy>(bindings: &mut HashMap<Action, T>, action: Action, input: T) {
+    let previous = bindings.insert(action, input);
+    let other = bindings
+        .iter()
+        .find(|(other, bound)| **other != action && **bound == input)
+        .map(|(other, _)| *other);
+    if let Some(other) = other {
+        match previous {
+            Some(previous) => bindings.insert(other, previous),
+            None => bindings.remove(&other),
+        };
+    }
+}
+
+// Keys and gamepad buttons are written by their name, as in `KeyCode::Escape`
+pub fn input_name<T: std::fmt::Debug>(input: T) -> String {
+    format!("{input:?}")
+}
+
+pub fn parse_input<T: FromReflect>(name: &str) -> Option<T> {
+    T::from_reflect(&DynamicEnum::new(name, DynamicVariant::Unit))
+}
+
+// Actions currently held by the player. Gameplay systems read this instead of the devices so
+// that the player can also be driven by an `InputScript`
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct PlayerInput(pub Input<Action>);
+
+// Live state of the actions on the keyboard and the active gamepad, through the bindings. Actions
+// that are not part of a run, such as pausing, are read from here even while a script plays
+#[derive(SystemParam)]
+pub struct ActionDevices<'w> {
+    keyboard: Res<'w, Input<KeyCode>>,
+    buttons: Res<'w, Input<GamepadButton>>,
+    gamepad: Res<'w, ActiveGamepad>,
+    bindings: Res<'w, Bindings>,
+}
+
+impl ActionDevices<'_> {
+    fn gamepad_button(&self, action: Action) -> Option<GamepadButton> {
+        Some(GamepadButton::new(
+            self.gamepad.0?,
+            self.bindings.button(action)?,
+        ))
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.bindings
+            .key(action)
+            .is_some_and(|key| self.keyboard.pressed(key))
+            || self
+                .gamepad_button(action)
+                .is_some_and(|button| self.buttons.pressed(button))
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.bindings
+            .key(action)
+            .is_some_and(|key| self.keyboard.just_pressed(key))
+            || self
+                .gamepad_button(action)
+                .is_some_and(|button| self.buttons.just_pressed(button))
+    }
+}
+
+// Left stick of the active gamepad once the deadzone is taken out, its length going from 0 at rest
+// to 1 when fully tilted. Each axis is a whole number of `STICK_STEPS`
+#[derive(Resource, Debug, Default, PartialEq, Clone, Copy, Deref, DerefMut)]
+pub struct PlayerStick(pub Vec2);
+
+// Gamepad the player is driven with, the first one connected
+#[derive(Resource, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ActiveGamepad(pub Option<Gamepad>);
+
+// Device the player used last, its controls are shown on screen
+#[derive(Resource, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    Gamepad,
+}
+
+// Tag component for the text telling which controls apply
+#[derive(Component)]
+struct DevicePrompt;
+
+// Rounds each axis of the stick to the closest step, as a number of `STICK_STEPS`
+pub fn quantize_stick(stick: Vec2) -> IVec2 {
+    (stick * STICK_STEPS).round().as_ivec2()
+}
+
+pub fn dequantize_stick(steps: IVec2) -> Vec2 {
+    steps.as_vec2() / STICK_STEPS
+}
+
+// Rescales the stick so that movement starts from 0 right past the deadzone, keeping its direction
+pub fn apply_deadzone(stick: Vec2, deadzone: f32) -> Vec2 {
+    let length = stick.length();
+    if length <= deadzone {
+        return Vec2::ZERO;
+    }
+    stick / length * ((length - deadzone) / (1.0 - deadzone)).min(1.0)
+}
+
+// Where `PlayerInput` gets filled from
+#[derive(Resource, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum InputSource {
+    #[default]
+    Keyboard,
+    Script,
+}
+
+// An action being pressed or released on a given frame of a script
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ScriptedAction {
+    pub frame: u32,
+    pub action: Action,
+    pub pressed: bool,
+}
+
+// Frame-indexed list of action presses. Frames are fixed timestep steps, frame 0 being the first
+// step spent in `GameState::Game`
+#[derive(Resource, Debug, Default, Clone)]
+pub struct InputScript {
+    actions: Vec<ScriptedAction>,
+    frame: u32,
+}
+
+impl InputScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn press(mut self, frame: u32, action: Action) -> Self {
+        self.actions.push(ScriptedAction {
+            frame,
+            action,
+            pressed: true,
+        });
+        self
+    }
+
+    pub fn release(mut self, frame: u32, action: Action) -> Self {
+        self.actions.push(ScriptedAction {
+            frame,
+            action,
+            pressed: false,
+        });
+        self
+    }
+
+    // Presses the action on frame `from` and releases it on frame `to`
+    pub fn hold(self, from: u32, to: u32, action: Action) -> Self {
+        self.press(from, action).release(to, action)
+    }
+
+    // Next frame that will be played
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.actions
+            .iter()
+            .all(|scripted| scripted.frame < self.frame)
+    }
+}
+
+pub struct PlayerInputPlugin;
+
+impl Plugin for PlayerInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerInput>()
+            .init_resource::<InputSource>()
+            .init_resource::<InputScript>()
+            .init_resource::<PlayerStick>()
+            .init_resource::<ActiveGamepad>()
+            .init_resource::<InputDevice>()
+            .init_resource::<Bindings>()
+            .add_systems(OnEnter(GameState::Game), (restart_script, spawn_prompt))
+            .add_systems(
+                PreUpdate,
+                (
+                    track_gamepads,
+                    (read_actions, read_stick)
+                        .chain()
+                        .run_if(resource_equals(InputSource::Keyboard)),
+                    detect_device,
+                )
+                    .chain()
+                    .after(InputSystem),
+            )
+            .add_systems(Update, show_prompt.run_if(in_state(GameState::Game)))
+            .add_systems(
+                FixedUpdate,
+                play_script
+                    .in_set(GameplaySet::Input)
+                    .run_if(resource_equals(InputSource::Script))
+                    .run_if(in_state(GameState::Game)),
+            );
+    }
+}
+
+fn restart_script(
+    mut script: ResMut<InputScript>,
+    mut input: ResMut<PlayerInput>,
+    mut stick: ResMut<PlayerStick>,
+) {
+    script.frame = 0;
+    input.reset_all();
+    *stick = PlayerStick::default();
+}
+
+// Gamepads can be plugged in and out at any time. Losing the active one hands over to any other
+// still connected
+fn track_gamepads(
+    mut connections: EventReader<GamepadConnectionEvent>,
+    gamepads: Res<Gamepads>,
+    mut active: ResMut<ActiveGamepad>,
+) {
+    for event in connections.read() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => {
+                info!(target: INPUT, "Gamepad {} connected: {}", event.gamepad.id, info.name);
+                if active.0.is_none() {
+                    active.0 = Some(event.gamepad);
+                }
+            }
+            GamepadConnection::Disconnected => {
+                info!(target: INPUT, "Gamepad {} disconnected", event.gamepad.id);
+                if active.0 == Some(event.gamepad) {
+                    active.0 = gamepads.iter().find(|gamepad| *gamepad != event.gamepad);
+                }
+            }
+        }
+    }
+}
+
+pub fn read_actions(devices: ActionDevices, mut input: ResMut<PlayerInput>) {
+    input.clear();
+    for action in Action::ALL {
+        match (devices.pressed(action), input.pressed(action)) {
+            (true, false) => input.press(action),
+            (false, true) => input.release(action),
+            _ => {}
+        }
+    }
+}
+
+fn read_stick(
+    active: Res<ActiveGamepad>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut stick: ResMut<PlayerStick>,
+) {
+    let Some(gamepad) = active.0 else {
+        stick.set_if_neq(PlayerStick::default());
+        return;
+    };
+    let axis = |axis_type| {
+        axes.get(GamepadAxis::new(gamepad, axis_type))
+            .unwrap_or_default()
+    };
+    let raw = Vec2::new(
+        axis(GamepadAxisType::LeftStickX),
+        axis(GamepadAxisType::LeftStickY),
+    );
+    let steps = quantize_stick(apply_deadzone(raw, STICK_DEADZONE));
+    stick.set_if_neq(PlayerStick(dequantize_stick(steps)));
+}
+
+// The last device touched is the active one
+fn detect_device(
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    stick: Res<PlayerStick>,
+    mut device: ResMut<InputDevice>,
+) {
+    if keyboard.get_just_pressed().next().is_some() {
+        device.set_if_neq(InputDevice::Keyboard);
+    } else if buttons.get_just_pressed().next().is_some() || stick.0 != Vec2::ZERO {
+        device.set_if_neq(InputDevice::Gamepad);
+    }
+}
+
+fn spawn_prompt(mut commands: Commands, assets: Res<GameAssets>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 20.0,
+                color: TEXT_COLOR,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            bottom: Val::Px(10.0),
+            ..default()
+        }),
+        DevicePrompt,
+        OnGameScreen,
+    ));
+}
+
+fn show_prompt(
+    device: Res<InputDevice>,
+    active: Res<ActiveGamepad>,
+    bindings: Res<Bindings>,
+    mut prompts: Query<(&mut Text, Ref<DevicePrompt>)>,
+) {
+    let moves = [
+        Action::MoveUp,
+        Action::MoveLeft,
+        Action::MoveDown,
+        Action::MoveRight,
+    ];
+    let keys = moves
+        .map(|action| bindings.key(action).map(input_name).unwrap_or_default())
+        .join(" ");
+    let buttons = moves
+        .map(|action| bindings.button(action).map(input_name).unwrap_or_default())
+        .join(" ");
+    let dash_key = bindings
+        .key(Action::Dash)
+        .map(input_name)
+        .unwrap_or_default();
+    let dash_button = bindings
+        .button(Action::Dash)
+        .map(input_name)
+        .unwrap_or_default();
+
+    for (mut text, prompt) in &mut prompts {
+        if !prompt.is_added() && !device.is_changed() && !active.is_changed() {
+            continue;
+        }
+        text.sections[0].value = match (*device, active.0) {
+            (InputDevice::Gamepad, Some(_)) => {
+                format!("Gamepad: left stick or {buttons} to move, {dash_button} to dash")
+            }
+            (InputDevice::Keyboard, Some(_)) => {
+                format!("Keyboard: {keys} to move, {dash_key} to dash, or pick up the gamepad")
+            }
+            (_, None) => format!("Keyboard: {keys} to move, {dash_key} to dash"),
+        };
+    }
+}
+
+fn play_script(mut script: ResMut<InputScript>, mut input: ResMut<PlayerInput>) {
+    input.clear();
+    let frame = script.frame;
+    for scripted in script
+        .actions
+        .iter()
+        .filter(|scripted| scripted.frame == frame)
+    {
+        if scripted.pressed {
+            input.press(scripted.action);
+        } else {
+            input.release(scripted.action);
+        }
+    }
+    script.frame += 1;
+}
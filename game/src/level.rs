@@ -0,0 +1,199 @@
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{consts, logging::LEVEL};
+
+const LEVEL_HEADER: &str = "passive-power-level 1";
+
+// Number of blocks a generated level tries to place
+const GENERATED_BLOCKS: usize = 60;
+
+// Where the player starts every level
+pub const PLAYER_START: Vec2 = Vec2::new(
+    consts::WINDOW_LEFT_X + 730.0,
+    consts::WINDOW_BOTTOM_Y + 50.0,
+);
+
+const FLAG_POSITION: Vec2 = Vec2::new(
+    consts::WINDOW_LEFT_X + 1480.0,
+    consts::WINDOW_BOTTOM_Y + 800.0,
+);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BlockKind {
+    Obstacle,
+    // Zombies come out of spawners
+    Spawner,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub position: Vec2,
+}
+
+// Layout of a level: where the flag is and where every block stands. A level can also pick a
+// playlist played over it, instead of the in-game music
+#[derive(Debug, PartialEq, Clone)]
+pub struct Level {
+    pub flag: Vec2,
+    pub blocks: Vec<Block>,
+    pub music: Vec<String>,
+}
+
+// Level loaded from a file, played instead of the generated ones
+#[derive(Resource, Debug, Clone)]
+pub struct CustomLevel(pub Level);
+
+impl Level {
+    // Places blocks at random, keeping the rows and columns of the flag, the player and the
+    // spawners clear
+    pub fn generate(seed: u64) -> Self {
+        let mut spots: Vec<(f32, f32)> = vec![
+            (FLAG_POSITION.x, FLAG_POSITION.y),
+            (PLAYER_START.x, PLAYER_START.y),
+        ];
+        let mut blocks = Vec::new();
+        let mut rng = StdRng::seed_from_u64(seed);
+        for c in 1..=GENERATED_BLOCKS {
+            let mut x: f32 =
+                rng.gen_range((consts::WINDOW_LEFT_X + 25.)..=(consts::WINDOW_WIDTH / 2. - 25.));
+            let mut y: f32 =
+                rng.gen_range((consts::WINDOW_BOTTOM_Y + 25.)..=(consts::WINDOW_HEIGHT / 2. - 25.));
+            let is_spawner: i32 = rng.gen_range(0..=1);
+
+            let mut i = 0;
+            while is_invalid_spot(spots.clone(), (x, y)) {
+                x = rng
+                    .gen_range((consts::WINDOW_LEFT_X + 25.)..=(consts::WINDOW_WIDTH / 2. - 25.));
+                y = rng.gen_range(
+                    (consts::WINDOW_BOTTOM_Y + 25.)..=(consts::WINDOW_HEIGHT / 2. - 25.),
+                );
+                i += 1;
+                if i > 100 {
+                    break;
+                }
+            }
+
+            if i > 100 {
+                debug!(target: LEVEL, "No room left for obstacle {c}");
+                continue;
+            }
+
+            if is_spawner == 1 {
+                spots.push((x, y));
+            }
+            trace!(target: LEVEL, "Placed obstacle {c} at ({x}, {y})");
+
+            blocks.push(Block {
+                kind: if is_spawner == 1 {
+                    BlockKind::Spawner
+                } else {
+                    BlockKind::Obstacle
+                },
+                position: Vec2::new(x, y),
+            });
+        }
+
+        Self {
+            flag: FLAG_POSITION,
+            blocks,
+            music: Vec::new(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_string())
+    }
+
+    // One item per line: `flag x y`, `obstacle x y`, `spawner x y` or `music path`, the path being
+    // relative to the assets folder. Music lines make up the playlist, in order. Empty lines and
+    // lines starting with `#` are ignored
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut lines = text.lines();
+        if lines.next() != Some(LEVEL_HEADER) {
+            return Err(invalid("not a level file".to_string()));
+        }
+
+        let mut flag = None;
+        let mut blocks = Vec::new();
+        let mut music = Vec::new();
+        for line in lines.map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(path) = line.strip_prefix("music ") {
+                music.push(path.trim().to_string());
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let [item, x, y] = words[..] else {
+                return Err(invalid(format!("malformed line: {line}")));
+            };
+            let (Ok(x), Ok(y)) = (x.parse(), y.parse()) else {
+                return Err(invalid(format!("bad position: {line}")));
+            };
+            let position = Vec2::new(x, y);
+            match item {
+                "flag" => flag = Some(position),
+                "obstacle" => blocks.push(Block {
+                    kind: BlockKind::Obstacle,
+                    position,
+                }),
+                "spawner" => blocks.push(Block {
+                    kind: BlockKind::Spawner,
+                    position,
+                }),
+                _ => return Err(invalid(format!("unknown item: {item}"))),
+            }
+        }
+
+        Ok(Self {
+            flag: flag.ok_or_else(|| invalid("missing flag".to_string()))?,
+            blocks,
+            music,
+        })
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{LEVEL_HEADER}")?;
+        writeln!(f, "flag {} {}", self.flag.x, self.flag.y)?;
+        for music in &self.music {
+            writeln!(f, "music {music}")?;
+        }
+        for block in &self.blocks {
+            let item = match block.kind {
+                BlockKind::Obstacle => "obstacle",
+                BlockKind::Spawner => "spawner",
+            };
+            writeln!(f, "{item} {} {}", block.position.x, block.position.y)?;
+        }
+        Ok(())
+    }
+}
+
+fn overlaps(fst: (f32, f32), snd: (f32, f32)) -> bool {
+    (fst.0 - 30. < snd.0 && snd.0 < fst.0 + 30.) || (fst.1 - 30. < snd.1 && snd.1 < fst.1 + 30.)
+}
+
+fn is_invalid_spot(spots: Vec<(f32, f32)>, new_spot: (f32, f32)) -> bool {
+    for spot in spots {
+        if overlaps(spot, new_spot) {
+            return true;
+        }
+    }
+    false
+}
@@ -1,5 +1,150 @@
+#![allow(clippy::type_complexity)]
+
+use bevy::prelude::*;
+
 pub mod animator;
+pub mod audio;
+pub mod cli;
 pub mod consts;
+pub mod debug;
+pub mod environment;
+pub mod events;
+pub mod ghost;
+pub mod headless;
+pub mod input;
+pub mod level;
+pub mod loading;
+pub mod logging;
+pub mod menu;
+pub mod physics;
+pub mod player;
+pub mod replay;
+pub mod settings;
+pub mod splash;
+
+pub const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+
+// Enum that will be used as a global state for the game
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
+pub enum GameState {
+    #[default]
+    Loading,
+    Splash,
+    Menu,
+    Game,
+}
+
+// One of the settings that can be set through the menu. It will be a resource in the app
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
+pub enum DisplayQuality {
+    Low,
+    Medium,
+    High,
+}
+
+// Loudness of a volume bus, set through the menu
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Volume(pub u32);
+
+impl Volume {
+    pub const MAX: u32 = 10;
+
+    // From 0.0 when silent to 1.0 at `MAX`
+    pub fn get_val(self) -> f64 {
+        self.0 as f64 / Self::MAX as f64
+    }
+}
+
+// Volume buses, each one is a resource in the app. The master bus scales the three others
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Deref)]
+pub struct MasterVolume(pub Volume);
+
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Deref)]
+pub struct MusicVolume(pub Volume);
+
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Deref)]
+pub struct SfxVolume(pub Volume);
+
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Deref)]
+pub struct UiVolume(pub Volume);
+
+impl From<Volume> for MasterVolume {
+    fn from(volume: Volume) -> Self {
+        Self(volume)
+    }
+}
+
+impl From<Volume> for MusicVolume {
+    fn from(volume: Volume) -> Self {
+        Self(volume)
+    }
+}
+
+impl From<Volume> for SfxVolume {
+    fn from(volume: Volume) -> Self {
+        Self(volume)
+    }
+}
+
+impl From<Volume> for UiVolume {
+    fn from(volume: Volume) -> Self {
+        Self(volume)
+    }
+}
+
+// Silences every bus without losing their volumes
+#[derive(Resource, Debug, Default, Component, PartialEq, Eq, Clone, Copy)]
+pub struct Muted(pub bool);
+
+#[derive(Component)]
+pub struct OnGameScreen;
+
+#[derive(Component)]
+pub struct WinFlag;
+
+#[derive(Component)]
+pub struct PlayerFlag;
+
+// Anything the player can walk over to collect
+#[derive(Component)]
+pub struct Pickup;
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameTimer(pub Timer);
+
+// The whole game bundled as a single plugin. It expects the windowing, rendering and physics
+// plugins to be added by the binary embedding it
+pub struct PassivePowerPlugin;
+
+impl Plugin for PassivePowerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(settings::SettingsPlugin)
+            .add_state::<GameState>()
+            .add_systems(Startup, setup)
+            .add_plugins(loading::LoadingPlugin)
+            .add_plugins(splash::SplashPlugin)
+            .add_plugins(menu::MenuPlugin)
+            .add_plugins(events::GameEventsPlugin)
+            .add_plugins(environment::PlatformsPlugin)
+            .add_plugins(player::PlayerPlugin)
+            .add_plugins(audio::GameAudioPlugin)
+            .add_plugins(replay::ReplayPlugin)
+            .add_plugins(ghost::GhostPlugin)
+            .add_plugins(debug::DebugPlugin)
+            .add_systems(OnExit(GameState::Game), despawn_screen::<OnGameScreen>);
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
+
+// Generic system that takes a component as a parameter, and will despawn all entities with that component
+pub fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
+    for entity in &to_despawn {
+        commands.entity(entity).despawn_recursive();
+    }
+}
 
 #[test]
 fn test() {
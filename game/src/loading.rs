@@ -0,0 +1,279 @@
+use bevy::{asset::LoadState, prelude::*};
+use bevy_kira_audio::AudioSource;
+
+use crate::{audio, despawn_screen, logging::ASSETS, GameState, TEXT_COLOR};
+
+pub const FONT: &str = "fonts/FiraSans-Bold.ttf";
+pub const SPLASH_ICON: &str = "branding/icon.png";
+pub const PLAYER_TEXTURE: &str = "texture/player.png";
+pub const FLAG_TEXTURE: &str = "texture/flag.png";
+pub const PLAY_ICON: &str = "textures/Game Icons/right.png";
+pub const SETTINGS_ICON: &str = "textures/Game Icons/wrench.png";
+pub const QUIT_ICON: &str = "textures/Game Icons/exitRight.png";
+
+// How long missing files stay listed on the loading screen before the game goes on without them
+const MISSING_FILES_DELAY: f32 = 3.0;
+
+// Every file the game needs, loaded once before the splash screen. Without a window, as in the
+// headless app, the handles are left as defaults. Tracks with no file found are left out
+#[derive(Resource, Debug, Default, Clone)]
+pub struct GameAssets {
+    pub font: Handle<Font>,
+    pub splash_icon: Handle<Image>,
+    pub player_texture: Handle<Image>,
+    pub flag_texture: Handle<Image>,
+    pub play_icon: Handle<Image>,
+    pub settings_icon: Handle<Image>,
+    pub quit_icon: Handle<Image>,
+    pub menu_music: Option<(&'static str, Handle<AudioSource>)>,
+    pub game_music: Option<(&'static str, Handle<AudioSource>)>,
+    pub splash_jingle: Option<(&'static str, Handle<AudioSource>)>,
+    pub hit_sound: Option<(&'static str, Handle<AudioSource>)>,
+    pub reward_sound: Option<(&'static str, Handle<AudioSource>)>,
+    pub footstep_sound: Option<(&'static str, Handle<AudioSource>)>,
+    pub click_sound: Option<(&'static str, Handle<AudioSource>)>,
+    pub pulse_layer: Option<(&'static str, Handle<AudioSource>)>,
+    pub drone_layer: Option<(&'static str, Handle<AudioSource>)>,
+    pub zombie_groan: Option<(&'static str, Handle<AudioSource>)>,
+    pub spawner_sound: Option<(&'static str, Handle<AudioSource>)>,
+}
+
+impl GameAssets {
+    pub fn load(server: &AssetServer) -> Self {
+        let assets_dir = audio::assets_dir();
+        let track = |name: &str, candidates: &[&'static str]| {
+            audio::find_track(name, candidates, &assets_dir).map(|path| (path, server.load(path)))
+        };
+
+        Self {
+            font: server.load(FONT),
+            splash_icon: server.load(SPLASH_ICON),
+            player_texture: server.load(PLAYER_TEXTURE),
+            flag_texture: server.load(FLAG_TEXTURE),
+            play_icon: server.load(PLAY_ICON),
+            settings_icon: server.load(SETTINGS_ICON),
+            quit_icon: server.load(QUIT_ICON),
+            menu_music: track("menu music", audio::MENU_MUSIC),
+            game_music: track("in-game music", audio::GAME_MUSIC),
+            splash_jingle: track("splash jingle", audio::SPLASH_JINGLE),
+            hit_sound: track("hit sound", audio::HIT_SOUND),
+            reward_sound: track("reward sound", audio::REWARD_SOUND),
+            footstep_sound: track("footstep sound", audio::FOOTSTEP_SOUND),
+            click_sound: track("click sound", audio::CLICK_SOUND),
+            pulse_layer: track("pulse layer", audio::PULSE_LAYER),
+            drone_layer: track("drone layer", audio::DRONE_LAYER),
+            zombie_groan: track("zombie groan", audio::ZOMBIE_GROAN),
+            spawner_sound: track("spawner sound", audio::SPAWNER_SOUND),
+        }
+    }
+
+    // Every handle along with the file it is loaded from
+    pub fn files(&self) -> Vec<(&'static str, UntypedHandle)> {
+        let tracks = [
+            &self.menu_music,
+            &self.game_music,
+            &self.splash_jingle,
+            &self.hit_sound,
+            &self.reward_sound,
+            &self.footstep_sound,
+            &self.click_sound,
+            &self.pulse_layer,
+            &self.drone_layer,
+            &self.zombie_groan,
+            &self.spawner_sound,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|(path, handle)| (*path, handle.clone().untyped()));
+
+        let mut files = vec![
+            (FONT, self.font.clone().untyped()),
+            (SPLASH_ICON, self.splash_icon.clone().untyped()),
+            (PLAYER_TEXTURE, self.player_texture.clone().untyped()),
+            (FLAG_TEXTURE, self.flag_texture.clone().untyped()),
+            (PLAY_ICON, self.play_icon.clone().untyped()),
+            (SETTINGS_ICON, self.settings_icon.clone().untyped()),
+            (QUIT_ICON, self.quit_icon.clone().untyped()),
+        ];
+        files.extend(tracks);
+        files
+    }
+}
+
+// State the game moves to once everything is loaded
+#[derive(Resource, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct AfterLoading(pub GameState);
+
+impl Default for AfterLoading {
+    fn default() -> Self {
+        Self(GameState::Splash)
+    }
+}
+
+// Files that could not be loaded, and how long they have been shown for
+#[derive(Resource, Default)]
+struct MissingFiles {
+    paths: Vec<&'static str>,
+    shown_for: f32,
+}
+
+// Tag component used to tag entities added on the loading screen
+#[derive(Component)]
+struct OnLoadingScreen;
+
+#[derive(Component)]
+struct ProgressBar;
+
+#[derive(Component)]
+struct ProgressText;
+
+// This plugin loads every asset up front and shows how far along it is, so that no screen is ever
+// drawn with textures or fonts still missing
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AfterLoading>()
+            .init_resource::<MissingFiles>()
+            .add_systems(OnEnter(GameState::Loading), (start_loading, loading_setup))
+            .add_systems(
+                Update,
+                (track_progress, wait_on_missing_files)
+                    .chain()
+                    .run_if(in_state(GameState::Loading)),
+            )
+            .add_systems(
+                OnExit(GameState::Loading),
+                despawn_screen::<OnLoadingScreen>,
+            );
+    }
+}
+
+fn start_loading(mut commands: Commands, server: Res<AssetServer>) {
+    commands.insert_resource(GameAssets::load(&server));
+}
+
+// The font is still loading at this point, so the screen sticks to Bevy's default one
+fn loading_setup(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnLoadingScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "Loading",
+                    TextStyle {
+                        font_size: 40.0,
+                        color: TEXT_COLOR,
+                        ..default()
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center),
+                ProgressText,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(400.0),
+                        height: Val::Px(20.0),
+                        margin: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: Color::CRIMSON.into(),
+                            ..default()
+                        },
+                        ProgressBar,
+                    ));
+                });
+        });
+}
+
+fn track_progress(
+    server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    after_loading: Res<AfterLoading>,
+    mut missing: ResMut<MissingFiles>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut bar: Query<&mut Style, With<ProgressBar>>,
+    mut text: Query<&mut Text, With<ProgressText>>,
+) {
+    if !missing.paths.is_empty() {
+        return;
+    }
+
+    let files = assets.files();
+    let mut done = 0;
+    let mut failed = Vec::new();
+    for (path, handle) in &files {
+        match server.get_load_state(handle.id()) {
+            Some(LoadState::Loaded) => done += 1,
+            Some(LoadState::Failed) => {
+                done += 1;
+                failed.push(*path);
+            }
+            _ => {}
+        }
+    }
+
+    for mut style in &mut bar {
+        style.width = Val::Percent(100.0 * done as f32 / files.len() as f32);
+    }
+    if done < files.len() {
+        for mut text in &mut text {
+            text.sections[0].value = format!("Loading {done}/{}", files.len());
+        }
+        return;
+    }
+
+    if failed.is_empty() {
+        game_state.set(after_loading.0);
+        return;
+    }
+    for path in &failed {
+        error!(target: ASSETS, "Missing asset file: assets/{path}");
+    }
+    for mut text in &mut text {
+        text.sections[0].value = format!("Missing files:\n{}", failed.join("\n"));
+    }
+    missing.paths = failed;
+}
+
+// The game still runs without the files that are missing, once they were shown for a while
+fn wait_on_missing_files(
+    time: Res<Time>,
+    after_loading: Res<AfterLoading>,
+    mut missing: ResMut<MissingFiles>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if missing.paths.is_empty() {
+        return;
+    }
+
+    missing.shown_for += time.delta_seconds();
+    if missing.shown_for >= MISSING_FILES_DELAY {
+        game_state.set(after_loading.0);
+    }
+}
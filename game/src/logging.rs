@@ -0,0 +1,61 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::Path,
+    sync::Mutex,
+};
+
+use bevy::prelude::*;
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+// Log targets of the game, so that each part can be filtered on its own with `RUST_LOG`, e.g.
+// `RUST_LOG=info,physics=debug,ai=trace`
+pub const PHYSICS: &str = "physics";
+pub const AI: &str = "ai";
+pub const AUDIO: &str = "audio";
+pub const LEVEL: &str = "level";
+pub const INPUT: &str = "input";
+pub const REPLAY: &str = "replay";
+pub const SETTINGS: &str = "settings";
+pub const ASSETS: &str = "assets";
+pub const LOGGING: &str = "logging";
+
+// Where the logs of the last session are written
+pub const LOG_PATH: &str = "logs/passive-power.log";
+
+// Filter used when `RUST_LOG` is not set, the graphics backends are very chatty otherwise. The
+// targets of the game are listed so that each one can be turned up or down from here
+pub const DEFAULT_FILTER: &str = "info,physics=info,ai=info,audio=info,level=info,input=info,\
+replay=info,settings=info,assets=info,logging=info,wgpu=error,naga=warn";
+
+// Sends the logs to the terminal and to the file at `path`. This replaces Bevy's `LogPlugin`,
+// which has to be disabled as only one logger can be installed
+pub fn init(path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let (file_layer, file_error) = match create_log_file(path) {
+        Ok(file) => (
+            Some(fmt::layer().with_ansi(false).with_writer(Mutex::new(file))),
+            None,
+        ),
+        Err(err) => (None, Some(err)),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(file_layer)
+        .init();
+
+    if let Some(err) = file_error {
+        warn!(target: LOGGING, "Could not write logs to {}: {err}", path.display());
+    }
+}
+
+fn create_log_file(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    File::create(path)
+}
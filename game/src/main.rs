@@ -1,89 +1,95 @@
-#![allow(clippy::type_complexity)]
-
-use bevy::{prelude::*, window::WindowResolution};
+use bevy::{
+    log::LogPlugin,
+    prelude::*,
+    window::{WindowMode, WindowResolution},
+};
 use bevy_rapier2d::prelude::*;
 
-mod audio;
-mod consts;
-mod environment;
-mod menu;
-mod player;
-mod splash;
-
-const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
-
-// Enum that will be used as a global state for the game
-#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
-enum GameState {
-    #[default]
-    Splash,
-    Menu,
-    Game,
-}
+use game::{
+    cli::{Args, USAGE},
+    consts,
+    headless::{self, HeadlessPlugin},
+    loading::AfterLoading,
+    logging,
+    physics::GamePhysicsPlugin,
+    replay::ReplayPlugin,
+    GameState, Muted, PassivePowerPlugin,
+};
 
-// One of the two settings that can be set through the menu. It will be a resource in the app
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
-enum DisplayQuality {
-    Low,
-    Medium,
-    High,
-}
+fn main() {
+    let args = match Args::from_env() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{USAGE}");
+        return;
+    }
 
-// One of the two settings that can be set through the menu. It will be a resource in the app
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
-struct Volume(u32);
+    logging::init(logging::LOG_PATH);
 
-impl Volume {
-    fn get_val(self) -> f64 {
-        self.0 as f64 / 10.0
+    let mut app = if args.headless {
+        headless_run()
+    } else {
+        windowed_game(&args)
+    };
+    if let Err(err) = args.apply_level(&mut app) {
+        error!(target: logging::LEVEL, "{err}");
+        std::process::exit(1);
     }
+    app.run();
 }
 
-#[derive(Component)]
-struct OnGameScreen;
-
-#[derive(Component)]
-struct WinFlag;
-
-#[derive(Component)]
-struct PlayerFlag;
-
-#[derive(Resource, Deref, DerefMut)]
-struct GameTimer(Timer);
-
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Passive Power".to_string(),
-                resolution: WindowResolution::new(consts::WINDOW_WIDTH, consts::WINDOW_HEIGHT),
-                resizable: false,
+fn windowed_game(args: &Args) -> App {
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "Passive Power".to_string(),
+                    resolution: WindowResolution::new(consts::WINDOW_WIDTH, consts::WINDOW_HEIGHT),
+                    resizable: false,
+                    mode: if args.fullscreen {
+                        WindowMode::BorderlessFullscreen
+                    } else {
+                        WindowMode::Windowed
+                    },
+                    ..Default::default()
+                }),
                 ..Default::default()
-            }),
-            ..Default::default()
-        }))
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(1e-12)) // Physics plugin
-        .add_plugins(RapierDebugRenderPlugin::default())
-        .insert_resource(DisplayQuality::Medium)
-        .insert_resource(Volume(7))
-        .add_state::<GameState>()
-        .add_systems(Startup, setup)
-        .add_plugins(splash::SplashPlugin)
-        .add_plugins(menu::MenuPlugin)
-        .add_plugins(environment::PlatformsPlugin)
-        .add_plugins(player::PlayerPlugin)
-        .add_plugins(audio::GameAudioPlugin)
-        .add_systems(OnExit(GameState::Game), despawn_screen::<OnGameScreen>)
-        .run();
-}
+            })
+            .disable::<LogPlugin>(),
+    )
+    .add_plugins(GamePhysicsPlugin) // Physics plugin
+    // Colliders are only drawn while the debug overlay is shown
+    .add_plugins(RapierDebugRenderPlugin {
+        enabled: false,
+        ..default()
+    });
 
-fn setup(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+    app.add_plugins(PassivePowerPlugin);
+    if args.starts_in_game() {
+        app.insert_resource(AfterLoading(GameState::Game));
+    } else if args.skip_splash {
+        app.insert_resource(AfterLoading(GameState::Menu));
+    }
+    if args.mute {
+        app.insert_resource(Muted(true));
+    }
+    app
 }
 
-// Generic system that takes a component as a parameter, and will despawn all entities with that component
-fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
-    for entity in &to_despawn {
-        commands.entity(entity).despawn_recursive();
-    }
+// A single replayed run without window nor audio, the app exits once the run is over
+fn headless_run() -> App {
+    let mut app = App::new();
+    app.add_plugins(HeadlessPlugin)
+        .add_plugins(ReplayPlugin)
+        .add_systems(Update, headless::exit_after_run);
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Game);
+    app
 }
@@ -1,11 +1,27 @@
-use bevy::{app::AppExit, prelude::*};
+use std::ops::Deref;
 
-use super::{despawn_screen, DisplayQuality, GameState, Volume, TEXT_COLOR};
+use bevy::{
+    app::AppExit,
+    input::InputSystem,
+    prelude::*,
+    ui::{RelativeCursorPosition, UiSystem},
+};
 
-// This plugin manages the menu, with 5 different screens:
-// - a main menu with "New Game", "Settings", "Quit"
-// - a settings menu with two submenus and a back button
+use crate::{
+    despawn_screen,
+    input::{input_name, Action, Bindings},
+    loading::GameAssets,
+    logging::REPLAY,
+    replay::{self, Replay, ReplayPath},
+    DisplayQuality, GameState, MasterVolume, MusicVolume, Muted, SfxVolume, UiVolume, Volume,
+    TEXT_COLOR,
+};
+
+// This plugin manages the menu, with 6 different screens:
+// - a main menu with "New Game", "Watch Replay", "Settings", "Quit"
+// - a settings menu with three submenus and a back button
 // - two settings screen with a setting that can be set and a back button
+// - a controls screen to rebind the key and the gamepad button of each action
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
@@ -42,16 +58,64 @@ impl Plugin for MenuPlugin {
             .add_systems(OnEnter(MenuState::SettingsSound), sound_settings_menu_setup)
             .add_systems(
                 Update,
-                setting_button::<Volume>.run_if(in_state(MenuState::SettingsSound)),
+                (
+                    setting_button::<Muted>,
+                    slider_setting::<MasterVolume>,
+                    slider_setting::<MusicVolume>,
+                    slider_setting::<SfxVolume>,
+                    slider_setting::<UiVolume>,
+                )
+                    .run_if(in_state(MenuState::SettingsSound)),
             )
             .add_systems(
                 OnExit(MenuState::SettingsSound),
                 despawn_screen::<OnSoundSettingsMenuScreen>,
             )
+            // Systems to handle the controls settings screen
+            .add_systems(
+                OnEnter(MenuState::SettingsControls),
+                controls_settings_menu_setup,
+            )
+            .add_systems(
+                Update,
+                (binding_button, show_bindings)
+                    .chain()
+                    .run_if(in_state(MenuState::SettingsControls)),
+            )
+            .add_systems(
+                PreUpdate,
+                capture_binding
+                    .after(InputSystem)
+                    .after(navigate_menu)
+                    .run_if(resource_exists::<AwaitingBinding>()),
+            )
+            .add_systems(
+                OnExit(MenuState::SettingsControls),
+                (
+                    despawn_screen::<OnControlsSettingsMenuScreen>,
+                    stop_awaiting_binding,
+                ),
+            )
             // Common systems to all screens that handles buttons behavior
             .add_systems(
                 Update,
                 (menu_action, button_system).run_if(in_state(GameState::Menu)),
+            )
+            // Common systems to all screens that handles sliders behavior
+            .add_systems(
+                Update,
+                (drag_slider, show_slider_value, show_slider_focus)
+                    .chain()
+                    .run_if(in_state(GameState::Menu)),
+            )
+            // Keyboard and gamepad navigation, before any system reacting to the buttons
+            .add_systems(
+                PreUpdate,
+                (release_key_presses, focus_hovered, navigate_menu)
+                    .chain()
+                    .after(UiSystem::Focus)
+                    .run_if(in_state(GameState::Menu))
+                    .run_if(not(resource_exists::<AwaitingBinding>())),
             );
     }
 }
@@ -63,6 +127,7 @@ enum MenuState {
     Settings,
     SettingsDisplay,
     SettingsSound,
+    SettingsControls,
     #[default]
     Disabled,
 }
@@ -83,6 +148,10 @@ struct OnDisplaySettingsMenuScreen;
 #[derive(Component)]
 struct OnSoundSettingsMenuScreen;
 
+// Tag component used to tag entities added on the controls settings menu screen
+#[derive(Component)]
+struct OnControlsSettingsMenuScreen;
+
 const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
 const HOVERED_PRESSED_BUTTON: Color = Color::rgb(0.25, 0.65, 0.25);
@@ -96,36 +165,264 @@ struct SelectedOption;
 #[derive(Component)]
 enum MenuButtonAction {
     Play,
+    WatchReplay,
     Settings,
     SettingsDisplay,
     SettingsSound,
+    SettingsControls,
+    ResetControls,
     BackToMainMenu,
     BackToSettings,
     Quit,
 }
 
-// This system handles changing all buttons color based on mouse interaction
+// This system handles changing all buttons color based on mouse interaction. The focused button
+// looks hovered
 fn button_system(
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, Option<&SelectedOption>),
-        (Changed<Interaction>, With<Button>),
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            Option<&SelectedOption>,
+            Option<&Focused>,
+        ),
+        With<Button>,
     >,
 ) {
-    for (interaction, mut color, selected) in &mut interaction_query {
-        *color = match (*interaction, selected) {
-            (Interaction::Pressed, _) | (Interaction::None, Some(_)) => PRESSED_BUTTON.into(),
-            (Interaction::Hovered, Some(_)) => HOVERED_PRESSED_BUTTON.into(),
-            (Interaction::Hovered, None) => HOVERED_BUTTON.into(),
-            (Interaction::None, None) => NORMAL_BUTTON.into(),
+    for (interaction, mut color, selected, focused) in &mut interaction_query {
+        let hovered = *interaction == Interaction::Hovered || focused.is_some();
+        let target = match (*interaction, hovered, selected) {
+            (Interaction::Pressed, _, _) | (_, false, Some(_)) => PRESSED_BUTTON,
+            (_, true, Some(_)) => HOVERED_PRESSED_BUTTON,
+            (_, true, None) => HOVERED_BUTTON,
+            (_, false, None) => NORMAL_BUTTON,
+        };
+        if color.0 != target {
+            color.0 = target;
+        }
+    }
+}
+
+// Inputs moving around the menus without a mouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+}
+
+impl MenuInput {
+    // Direction of a move on screen, where y points down as in the UI
+    pub fn direction(self) -> Option<Vec2> {
+        match self {
+            MenuInput::Up => Some(Vec2::NEG_Y),
+            MenuInput::Down => Some(Vec2::Y),
+            MenuInput::Left => Some(Vec2::NEG_X),
+            MenuInput::Right => Some(Vec2::X),
+            MenuInput::Confirm | MenuInput::Back => None,
+        }
+    }
+}
+
+const MENU_KEYS: [(KeyCode, MenuInput); 11] = [
+    (KeyCode::Up, MenuInput::Up),
+    (KeyCode::W, MenuInput::Up),
+    (KeyCode::Down, MenuInput::Down),
+    (KeyCode::S, MenuInput::Down),
+    (KeyCode::Left, MenuInput::Left),
+    (KeyCode::A, MenuInput::Left),
+    (KeyCode::Right, MenuInput::Right),
+    (KeyCode::D, MenuInput::Right),
+    (KeyCode::Return, MenuInput::Confirm),
+    (KeyCode::Space, MenuInput::Confirm),
+    (KeyCode::Escape, MenuInput::Back),
+];
+
+const MENU_GAMEPAD_BUTTONS: [(GamepadButtonType, MenuInput); 6] = [
+    (GamepadButtonType::DPadUp, MenuInput::Up),
+    (GamepadButtonType::DPadDown, MenuInput::Down),
+    (GamepadButtonType::DPadLeft, MenuInput::Left),
+    (GamepadButtonType::DPadRight, MenuInput::Right),
+    (GamepadButtonType::South, MenuInput::Confirm),
+    (GamepadButtonType::East, MenuInput::Back),
+];
+
+// Tag component used to mark the button the keyboard and the gamepad act on
+#[derive(Component)]
+struct Focused;
+
+// Tag component used to mark a button pressed with the keyboard or the gamepad. The press only
+// lasts a frame, as there is no mouse button to release
+#[derive(Component)]
+struct PressedByKey;
+
+// Closest button from `from` in `direction`, favouring the ones straight ahead over the ones off
+// to the side
+pub fn next_focus(
+    from: Vec2,
+    direction: Vec2,
+    candidates: impl IntoIterator<Item = (Entity, Vec2)>,
+) -> Option<Entity> {
+    candidates
+        .into_iter()
+        .filter_map(|(entity, position)| {
+            let offset = position - from;
+            let ahead = offset.dot(direction);
+            let aside = offset.perp_dot(direction).abs();
+            (ahead > 1.0).then_some((entity, ahead + 2.0 * aside))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity)
+}
+
+fn menu_inputs(
+    keys: &Input<KeyCode>,
+    gamepads: &Gamepads,
+    gamepad_buttons: &Input<GamepadButton>,
+) -> Vec<MenuInput> {
+    let keys = MENU_KEYS
+        .iter()
+        .filter(|(key, _)| keys.just_pressed(*key))
+        .map(|(_, input)| *input);
+    let buttons = gamepads.iter().flat_map(|gamepad| {
+        MENU_GAMEPAD_BUTTONS
+            .iter()
+            .filter(move |(button, _)| {
+                gamepad_buttons.just_pressed(GamepadButton::new(gamepad, *button))
+            })
+            .map(|(_, input)| *input)
+    });
+    keys.chain(buttons).collect()
+}
+
+fn release_key_presses(
+    mut commands: Commands,
+    mut pressed: Query<(Entity, &mut Interaction), With<PressedByKey>>,
+) {
+    for (entity, mut interaction) in &mut pressed {
+        if *interaction == Interaction::Pressed {
+            *interaction = Interaction::None;
+        }
+        commands.entity(entity).remove::<PressedByKey>();
+    }
+}
+
+fn move_focus(commands: &mut Commands, focused: &mut Option<Entity>, next: Entity) {
+    if let Some(previous) = focused.replace(next) {
+        commands.entity(previous).remove::<Focused>();
+    }
+    commands.entity(next).insert(Focused);
+}
+
+// The focus follows the mouse, so that keys pick up from the last button hovered
+fn focus_hovered(
+    mut commands: Commands,
+    hovered: Query<(Entity, &Interaction), (Changed<Interaction>, With<Button>)>,
+    focused: Query<Entity, With<Focused>>,
+) {
+    let mut current = focused.iter().next();
+    for (entity, interaction) in &hovered {
+        if *interaction == Interaction::Hovered && current != Some(entity) {
+            move_focus(&mut commands, &mut current, entity);
+        }
+    }
+}
+
+// Moves the focus between the buttons of the screen, presses the focused one, and goes back to
+// the previous screen. Left and right move a focused slider rather than the focus
+fn navigate_menu(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    menu_state: Res<State<MenuState>>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut buttons: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &mut Interaction,
+            Option<&mut Slider>,
+            Option<&Focused>,
+        ),
+        With<Button>,
+    >,
+) {
+    let mut focused = buttons
+        .iter()
+        .find(|(.., focused)| focused.is_some())
+        .map(|(entity, ..)| entity);
+
+    for input in menu_inputs(&keys, &gamepads, &gamepad_buttons) {
+        if input == MenuInput::Back {
+            match menu_state.get() {
+                MenuState::Settings => next_menu_state.set(MenuState::Main),
+                MenuState::SettingsDisplay
+                | MenuState::SettingsSound
+                | MenuState::SettingsControls => {
+                    next_menu_state.set(MenuState::Settings);
+                }
+                MenuState::Main | MenuState::Disabled => {}
+            }
+            continue;
+        }
+
+        // Nothing focused yet, the first button of the screen takes the focus
+        let Some(current) = focused else {
+            let first = buttons
+                .iter()
+                .map(|(entity, transform, ..)| (entity, transform.translation().truncate()))
+                .min_by(|a, b| a.1.y.total_cmp(&b.1.y).then(a.1.x.total_cmp(&b.1.x)));
+            if let Some((first, _)) = first {
+                move_focus(&mut commands, &mut focused, first);
+            }
+            continue;
+        };
+        let Ok((_, transform, mut interaction, slider, _)) = buttons.get_mut(current) else {
+            continue;
+        };
+        let from = transform.translation().truncate();
+        let direction = match (input, slider) {
+            (MenuInput::Left, Some(mut slider)) => {
+                slider.step(-1);
+                continue;
+            }
+            (MenuInput::Right, Some(mut slider)) => {
+                slider.step(1);
+                continue;
+            }
+            (MenuInput::Confirm, slider) => {
+                if slider.is_none() {
+                    *interaction = Interaction::Pressed;
+                    commands.entity(current).insert(PressedByKey);
+                }
+                continue;
+            }
+            (input, _) => input.direction(),
+        };
+        let Some(direction) = direction else {
+            continue;
+        };
+
+        let candidates = buttons
+            .iter()
+            .filter(|(entity, ..)| *entity != current)
+            .map(|(entity, transform, ..)| (entity, transform.translation().truncate()));
+        if let Some(next) = next_focus(from, direction, candidates) {
+            move_focus(&mut commands, &mut focused, next);
         }
     }
 }
 
 // This system updates the settings when a new value for a setting is selected, and marks
-// the button as the one currently selected
+// the button as the one currently selected. Several settings can share a screen, each one having
+// its own selected button
 fn setting_button<T: Resource + Component + PartialEq + Copy>(
     interaction_query: Query<(&Interaction, &T, Entity), (Changed<Interaction>, With<Button>)>,
-    mut selected_query: Query<(Entity, &mut BackgroundColor), With<SelectedOption>>,
+    mut selected_query: Query<(Entity, &mut BackgroundColor), (With<SelectedOption>, With<T>)>,
     mut commands: Commands,
     mut setting: ResMut<T>,
 ) {
@@ -140,11 +437,203 @@ fn setting_button<T: Resource + Component + PartialEq + Copy>(
     }
 }
 
+// Numeric settings that can be edited with a slider, from 0 to `MAX`
+pub trait SliderSetting: Resource + Component + PartialEq + Copy {
+    const MAX: u32;
+
+    fn value(&self) -> u32;
+
+    fn from_value(value: u32) -> Self;
+}
+
+// Every volume bus is edited with a slider
+impl<T> SliderSetting for T
+where
+    T: Resource + Component + PartialEq + Copy + Deref<Target = Volume> + From<Volume>,
+{
+    const MAX: u32 = Volume::MAX;
+
+    fn value(&self) -> u32 {
+        self.0
+    }
+
+    fn from_value(value: u32) -> Self {
+        Volume(value).into()
+    }
+}
+
+// A horizontal track filled up to its value. It is dragged with the mouse, or moved left and right
+// with the keyboard or the gamepad once focused
+#[derive(Component, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Slider {
+    pub value: u32,
+    pub max: u32,
+}
+
+impl Slider {
+    pub fn new(value: u32, max: u32) -> Self {
+        Self {
+            value: value.min(max),
+            max,
+        }
+    }
+
+    pub fn step(&mut self, delta: i32) {
+        self.value = self.value.saturating_add_signed(delta).min(self.max);
+    }
+
+    // `x` goes from 0 on the left end of the track to 1 on its right end, the value snaps to the
+    // closest step
+    pub fn set_from_cursor(&mut self, x: f32) {
+        self.value = (x.clamp(0.0, 1.0) * self.max as f32).round() as u32;
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.max == 0 {
+            0.0
+        } else {
+            self.value as f32 / self.max as f32
+        }
+    }
+}
+
+#[derive(Component)]
+struct SliderFill;
+
+#[derive(Component)]
+struct SliderLabel;
+
+// Spawns a labelled slider editing the setting `T`, starting at `setting`
+fn spawn_slider<T: SliderSetting>(
+    parent: &mut ChildBuilder,
+    label: &str,
+    setting: T,
+    text_style: &TextStyle,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                margin: UiRect::vertical(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: Color::CRIMSON.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(label, text_style.clone()).with_style(Style {
+                    width: Val::Px(200.0),
+                    margin: UiRect::horizontal(Val::Px(20.0)),
+                    ..default()
+                }),
+            );
+            let slider = Slider::new(setting.value(), T::MAX);
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(300.0),
+                            height: Val::Px(50.0),
+                            margin: UiRect::horizontal(Val::Px(20.0)),
+                            border: UiRect::all(Val::Px(3.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: NORMAL_BUTTON.into(),
+                        border_color: Color::NONE.into(),
+                        ..default()
+                    },
+                    RelativeCursorPosition::default(),
+                    slider,
+                    setting,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(0.0),
+                                width: Val::Percent(100.0 * slider.fraction()),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: PRESSED_BUTTON.into(),
+                            ..default()
+                        },
+                        SliderFill,
+                    ));
+                    parent.spawn((
+                        TextBundle::from_section(
+                            format!("{}/{}", slider.value, slider.max),
+                            text_style.clone(),
+                        ),
+                        SliderLabel,
+                    ));
+                });
+        });
+}
+
+// A pressed slider moves under the cursor for as long as the button is held
+fn drag_slider(mut sliders: Query<(&Interaction, &RelativeCursorPosition, &mut Slider)>) {
+    for (interaction, cursor, mut slider) in &mut sliders {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(position) = cursor.normalized {
+            let mut dragged = *slider;
+            dragged.set_from_cursor(position.x);
+            slider.set_if_neq(dragged);
+        }
+    }
+}
+
+fn show_slider_value(
+    sliders: Query<(&Slider, &Children), Changed<Slider>>,
+    mut fills: Query<&mut Style, With<SliderFill>>,
+    mut labels: Query<&mut Text, With<SliderLabel>>,
+) {
+    for (slider, children) in &sliders {
+        for child in children {
+            if let Ok(mut style) = fills.get_mut(*child) {
+                style.width = Val::Percent(100.0 * slider.fraction());
+            }
+            if let Ok(mut text) = labels.get_mut(*child) {
+                text.sections[0].value = format!("{}/{}", slider.value, slider.max);
+            }
+        }
+    }
+}
+
+fn show_slider_focus(mut sliders: Query<(&mut BorderColor, Option<&Focused>), With<Slider>>) {
+    for (mut border, focused) in &mut sliders {
+        let color = if focused.is_some() {
+            TEXT_COLOR
+        } else {
+            Color::NONE
+        };
+        if border.0 != color {
+            border.0 = color;
+        }
+    }
+}
+
+// The setting follows its slider live, so that the change can be heard right away
+fn slider_setting<T: SliderSetting>(
+    sliders: Query<&Slider, (Changed<Slider>, With<T>)>,
+    mut setting: ResMut<T>,
+) {
+    for slider in &sliders {
+        setting.set_if_neq(T::from_value(slider.value));
+    }
+}
+
 fn menu_setup(mut menu_state: ResMut<NextState<MenuState>>) {
     menu_state.set(MenuState::Main);
 }
 
-fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn main_menu_setup(mut commands: Commands, assets: Res<GameAssets>) {
     // Common style for all buttons on the screen
     let button_style = Style {
         width: Val::Px(250.0),
@@ -210,8 +699,9 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         }),
                     );
 
-                    // Display three buttons for each action available from the main menu:
+                    // Display four buttons for each action available from the main menu:
                     // - new game
+                    // - watch replay
                     // - settings
                     // - quit
                     parent
@@ -224,7 +714,7 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                             MenuButtonAction::Play,
                         ))
                         .with_children(|parent| {
-                            let icon = asset_server.load("textures/Game Icons/right.png");
+                            let icon = assets.play_icon.clone();
                             parent.spawn(ImageBundle {
                                 style: button_icon_style.clone(),
                                 image: UiImage::new(icon),
@@ -235,6 +725,27 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                                 button_text_style.clone(),
                             ));
                         });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: NORMAL_BUTTON.into(),
+                                ..default()
+                            },
+                            MenuButtonAction::WatchReplay,
+                        ))
+                        .with_children(|parent| {
+                            let icon = assets.play_icon.clone();
+                            parent.spawn(ImageBundle {
+                                style: button_icon_style.clone(),
+                                image: UiImage::new(icon),
+                                ..default()
+                            });
+                            parent.spawn(TextBundle::from_section(
+                                "Watch Replay",
+                                button_text_style.clone(),
+                            ));
+                        });
                     parent
                         .spawn((
                             ButtonBundle {
@@ -245,7 +756,7 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                             MenuButtonAction::Settings,
                         ))
                         .with_children(|parent| {
-                            let icon = asset_server.load("textures/Game Icons/wrench.png");
+                            let icon = assets.settings_icon.clone();
                             parent.spawn(ImageBundle {
                                 style: button_icon_style.clone(),
                                 image: UiImage::new(icon),
@@ -266,7 +777,7 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                             MenuButtonAction::Quit,
                         ))
                         .with_children(|parent| {
-                            let icon = asset_server.load("textures/Game Icons/exitRight.png");
+                            let icon = assets.quit_icon.clone();
                             parent.spawn(ImageBundle {
                                 style: button_icon_style,
                                 image: UiImage::new(icon),
@@ -323,6 +834,7 @@ fn settings_menu_setup(mut commands: Commands) {
                     for (action, text) in [
                         (MenuButtonAction::SettingsDisplay, "Display"),
                         (MenuButtonAction::SettingsSound, "Sound"),
+                        (MenuButtonAction::SettingsControls, "Controls"),
                         (MenuButtonAction::BackToMainMenu, "Back"),
                     ] {
                         parent
@@ -449,7 +961,14 @@ fn display_settings_menu_setup(mut commands: Commands, display_quality: Res<Disp
         });
 }
 
-fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
+fn sound_settings_menu_setup(
+    mut commands: Commands,
+    master: Res<MasterVolume>,
+    music: Res<MusicVolume>,
+    sfx: Res<SfxVolume>,
+    ui: Res<UiVolume>,
+    muted: Res<Muted>,
+) {
     let button_style = Style {
         width: Val::Px(200.0),
         height: Val::Px(65.0),
@@ -490,6 +1009,12 @@ fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
                     ..default()
                 })
                 .with_children(|parent| {
+                    // One slider per volume bus
+                    spawn_slider(parent, "Master", *master, &button_text_style);
+                    spawn_slider(parent, "Music", *music, &button_text_style);
+                    spawn_slider(parent, "Effects", *sfx, &button_text_style);
+                    spawn_slider(parent, "Interface", *ui, &button_text_style);
+
                     parent
                         .spawn(NodeBundle {
                             style: Style {
@@ -500,24 +1025,35 @@ fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
                             ..default()
                         })
                         .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section(
-                                "Volume",
-                                button_text_style.clone(),
-                            ));
-                            for volume_setting in [0, 1, 2, 3, 4, 5, 6, 7, 8, 9] {
+                            parent.spawn(
+                                TextBundle::from_section("Mute", button_text_style.clone())
+                                    .with_style(Style {
+                                        width: Val::Px(200.0),
+                                        margin: UiRect::horizontal(Val::Px(20.0)),
+                                        ..default()
+                                    }),
+                            );
+                            for (muted_setting, text) in
+                                [(Muted(false), "Off"), (Muted(true), "On")]
+                            {
                                 let mut entity = parent.spawn((
                                     ButtonBundle {
                                         style: Style {
-                                            width: Val::Px(30.0),
-                                            height: Val::Px(65.0),
+                                            width: Val::Px(100.0),
                                             ..button_style.clone()
                                         },
                                         background_color: NORMAL_BUTTON.into(),
                                         ..default()
                                     },
-                                    Volume(volume_setting),
+                                    muted_setting,
                                 ));
-                                if *volume == Volume(volume_setting) {
+                                entity.with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        text,
+                                        button_text_style.clone(),
+                                    ));
+                                });
+                                if *muted == muted_setting {
                                     entity.insert(SelectedOption);
                                 }
                             }
@@ -538,6 +1074,215 @@ fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
         });
 }
 
+// Device a binding is made on
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum BindingDevice {
+    Keyboard,
+    Gamepad,
+}
+
+// Button rebinding an action, its text shows what the action is currently bound to
+#[derive(Component, Debug, PartialEq, Eq, Clone, Copy)]
+struct BindingButton {
+    action: Action,
+    device: BindingDevice,
+}
+
+// Binding waiting for a key or a gamepad button to be pressed. Menu navigation is on hold
+// meanwhile, so that any key can be bound
+#[derive(Resource, Debug, PartialEq, Eq, Clone, Copy)]
+struct AwaitingBinding(BindingButton);
+
+fn controls_settings_menu_setup(mut commands: Commands) {
+    let button_style = Style {
+        width: Val::Px(200.0),
+        height: Val::Px(50.0),
+        margin: UiRect::all(Val::Px(5.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 30.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnControlsSettingsMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::CRIMSON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    // One row per action, with its key then its gamepad button
+                    for action in Action::ALL {
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::CRIMSON.into(),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn(
+                                    TextBundle::from_section(
+                                        action.label(),
+                                        button_text_style.clone(),
+                                    )
+                                    .with_style(Style {
+                                        width: Val::Px(200.0),
+                                        margin: UiRect::horizontal(Val::Px(20.0)),
+                                        ..default()
+                                    }),
+                                );
+                                for device in [BindingDevice::Keyboard, BindingDevice::Gamepad] {
+                                    parent
+                                        .spawn((
+                                            ButtonBundle {
+                                                style: button_style.clone(),
+                                                background_color: NORMAL_BUTTON.into(),
+                                                ..default()
+                                            },
+                                            BindingButton { action, device },
+                                        ))
+                                        .with_children(|parent| {
+                                            parent.spawn(TextBundle::from_section(
+                                                "",
+                                                button_text_style.clone(),
+                                            ));
+                                        });
+                                }
+                            });
+                    }
+
+                    parent
+                        .spawn(NodeBundle {
+                            background_color: Color::CRIMSON.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (action, text) in [
+                                (MenuButtonAction::ResetControls, "Defaults"),
+                                (MenuButtonAction::BackToSettings, "Back"),
+                            ] {
+                                parent
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: button_style.clone(),
+                                            background_color: NORMAL_BUTTON.into(),
+                                            ..default()
+                                        },
+                                        action,
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn(TextBundle::from_section(
+                                            text,
+                                            button_text_style.clone(),
+                                        ));
+                                    });
+                            }
+                        });
+                });
+        });
+}
+
+fn binding_button(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &BindingButton), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, binding) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            commands.insert_resource(AwaitingBinding(*binding));
+        }
+    }
+}
+
+// Binds the first key or gamepad button pressed. Escape cancels the binding on both devices, except
+// for the pause key: Escape is its default, and has to be bindable to it again
+fn capture_binding(
+    mut commands: Commands,
+    awaiting: Res<AwaitingBinding>,
+    keys: Res<Input<KeyCode>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut bindings: ResMut<Bindings>,
+) {
+    let BindingButton { action, device } = awaiting.0;
+    let escape_binds = device == BindingDevice::Keyboard && action == Action::Pause;
+    if escape_binds || !keys.just_pressed(KeyCode::Escape) {
+        match device {
+            BindingDevice::Keyboard => {
+                let Some(key) = keys.get_just_pressed().next() else {
+                    return;
+                };
+                bindings.bind_key(action, *key);
+            }
+            BindingDevice::Gamepad => {
+                // Buttons without a name could not be written to the settings
+                let Some(button) = gamepad_buttons
+                    .get_just_pressed()
+                    .map(|button| button.button_type)
+                    .find(|button| !matches!(button, GamepadButtonType::Other(_)))
+                else {
+                    return;
+                };
+                bindings.bind_button(action, button);
+            }
+        }
+    }
+    commands.remove_resource::<AwaitingBinding>();
+}
+
+fn stop_awaiting_binding(mut commands: Commands) {
+    commands.remove_resource::<AwaitingBinding>();
+}
+
+fn show_bindings(
+    bindings: Res<Bindings>,
+    awaiting: Option<Res<AwaitingBinding>>,
+    buttons: Query<(&BindingButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (binding, children) in &buttons {
+        let bound = match binding.device {
+            BindingDevice::Keyboard => bindings.key(binding.action).map(input_name),
+            BindingDevice::Gamepad => bindings.button(binding.action).map(input_name),
+        };
+        let value = if awaiting.as_deref() == Some(&AwaitingBinding(*binding)) {
+            "Press...".to_string()
+        } else {
+            bound.unwrap_or_else(|| "-".to_string())
+        };
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            if text.sections[0].value != value {
+                text.sections[0].value = value.clone();
+            }
+        }
+    }
+}
+
 fn menu_action(
     interaction_query: Query<
         (&Interaction, &MenuButtonAction),
@@ -546,6 +1291,9 @@ fn menu_action(
     mut app_exit_events: EventWriter<AppExit>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut game_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+    replay_path: Res<ReplayPath>,
+    mut bindings: ResMut<Bindings>,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
@@ -555,6 +1303,16 @@ fn menu_action(
                     game_state.set(GameState::Game);
                     menu_state.set(MenuState::Disabled);
                 }
+                MenuButtonAction::WatchReplay => match Replay::load(&replay_path.0) {
+                    Ok(replay) => {
+                        replay::watch(&mut commands, replay);
+                        game_state.set(GameState::Game);
+                        menu_state.set(MenuState::Disabled);
+                    }
+                    Err(err) => {
+                        warn!(target: REPLAY, "Could not load replay {}: {err}", replay_path.0.display());
+                    }
+                },
                 MenuButtonAction::Settings => menu_state.set(MenuState::Settings),
                 MenuButtonAction::SettingsDisplay => {
                     menu_state.set(MenuState::SettingsDisplay);
@@ -562,6 +1320,10 @@ fn menu_action(
                 MenuButtonAction::SettingsSound => {
                     menu_state.set(MenuState::SettingsSound);
                 }
+                MenuButtonAction::SettingsControls => {
+                    menu_state.set(MenuState::SettingsControls);
+                }
+                MenuButtonAction::ResetControls => *bindings = Bindings::default(),
                 MenuButtonAction::BackToMainMenu => menu_state.set(MenuState::Main),
                 MenuButtonAction::BackToSettings => {
                     menu_state.set(MenuState::Settings);
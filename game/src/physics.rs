@@ -0,0 +1,253 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_rapier2d::prelude::*;
+
+use crate::consts;
+
+// Length of a gameplay and physics step, the game always advances by this much at a time so
+// that runs play out the same regardless of the frame rate
+pub const FIXED_TIMESTEP: Duration = Duration::from_nanos(16_666_667);
+
+// Gameplay systems running on the fixed timestep, in the order they run before the physics step
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub enum GameplaySet {
+    // Player input for the step is gathered
+    Input,
+    // Collisions from the previous physics step are turned into gameplay events
+    Events,
+    // The game reacts to the input and to the previous physics step
+    Logic,
+}
+
+// Position of a moving body at the two last physics steps. Its `Transform` is blended between
+// them while rendering, and restored before the next step
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct PhysicsInterpolation {
+    previous: Vec3,
+    current: Vec3,
+    rendered: Vec3,
+}
+
+// Collision layers every collider belongs to. Colliders only interact with the layers listed in
+// `Layer::interacts_with`, and the rules are symmetric
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Layer {
+    Player,
+    Enemy,
+    Terrain,
+    Trigger,
+    Projectile,
+}
+
+impl Layer {
+    pub fn group(self) -> Group {
+        match self {
+            Layer::Player => Group::GROUP_1,
+            Layer::Enemy => Group::GROUP_2,
+            Layer::Terrain => Group::GROUP_3,
+            Layer::Trigger => Group::GROUP_4,
+            Layer::Projectile => Group::GROUP_5,
+        }
+    }
+
+    // Zombies walk through terrain, spawners included, and triggers only ever detect the player
+    pub fn interacts_with(self) -> Group {
+        match self {
+            Layer::Player => Layer::Enemy.group() | Layer::Terrain.group() | Layer::Trigger.group(),
+            Layer::Enemy => Layer::Player.group() | Layer::Projectile.group(),
+            Layer::Terrain => Layer::Player.group() | Layer::Projectile.group(),
+            Layer::Trigger => Layer::Player.group(),
+            Layer::Projectile => Layer::Enemy.group() | Layer::Terrain.group(),
+        }
+    }
+
+    // Triggers are sensors and never push anything back
+    pub fn solves_with(self) -> Group {
+        match self {
+            Layer::Trigger => Group::NONE,
+            _ => self.interacts_with() & !Layer::Trigger.group(),
+        }
+    }
+
+    // Components to insert on a collider of this layer
+    pub fn groups(self) -> (CollisionGroups, SolverGroups) {
+        (
+            CollisionGroups::new(self.group(), self.interacts_with()),
+            SolverGroups::new(self.group(), self.solves_with()),
+        )
+    }
+}
+
+// Tuning of the physics. Insert it before `GamePhysicsPlugin` to use another scale, the other
+// fields can be changed at any time
+#[derive(Resource, Debug, PartialEq, Clone, Copy)]
+pub struct PhysicsSettings {
+    // How many pixels make a meter for Rapier, only read when the plugin is built
+    pub pixels_per_meter: f32,
+    // In pixels per second squared. The game is seen from above, so nothing falls by default
+    pub gravity: Vec2,
+    // Rapier steps run for every fixed timestep
+    pub substeps: usize,
+    // Gap in pixels the player keeps from whatever it walks into
+    pub character_offset: f32,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            pixels_per_meter: consts::PIXELS_PER_METER,
+            gravity: Vec2::ZERO,
+            substeps: 1,
+            character_offset: 1.0,
+        }
+    }
+}
+
+impl PhysicsSettings {
+    // Character controller set up for a top-down game: there is no ground to snap to or steps to
+    // climb, obstacles are only slid along
+    pub fn character_controller(&self) -> KinematicCharacterController {
+        KinematicCharacterController {
+            offset: CharacterLength::Absolute(self.character_offset),
+            autostep: None,
+            snap_to_ground: None,
+            // Triggers are walked through, they only report that the player entered them
+            filter_flags: QueryFilterFlags::EXCLUDE_SENSORS,
+            // Only terrain stops the player. Zombies are walked into, so that their contact is
+            // reported and a dash goes through them
+            filter_groups: Some(CollisionGroups::new(
+                Layer::Player.group(),
+                Layer::Terrain.group(),
+            )),
+            ..default()
+        }
+    }
+}
+
+// This plugin runs Rapier and the gameplay on the fixed timestep, and smooths the rendering of
+// moving bodies in between steps
+pub struct GamePhysicsPlugin;
+
+impl Plugin for GamePhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = *app
+            .world
+            .get_resource_or_insert_with(PhysicsSettings::default);
+
+        app.insert_resource(Time::<Fixed>::from_duration(FIXED_TIMESTEP))
+            .add_plugins(
+                RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(settings.pixels_per_meter)
+                    .with_default_system_setup(false),
+            )
+            .configure_sets(
+                FixedUpdate,
+                (
+                    GameplaySet::Input,
+                    GameplaySet::Events,
+                    GameplaySet::Logic,
+                    PhysicsSet::SyncBackend,
+                    PhysicsSet::StepSimulation,
+                    PhysicsSet::Writeback,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::SyncBackend)
+                        .in_set(PhysicsSet::SyncBackend),
+                    RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::StepSimulation)
+                        .in_set(PhysicsSet::StepSimulation),
+                    RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::Writeback)
+                        .in_set(PhysicsSet::Writeback),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    apply_settings.run_if(resource_changed::<PhysicsSettings>()),
+                    restore_transforms,
+                )
+                    .before(GameplaySet::Input),
+            )
+            .add_systems(FixedUpdate, store_transforms.after(PhysicsSet::Writeback))
+            .add_systems(
+                PostUpdate,
+                (init_interpolation, interpolate_transforms)
+                    .chain()
+                    .before(TransformSystem::TransformPropagate),
+            );
+
+        configure_rapier(
+            &mut app.world.resource_mut::<RapierConfiguration>(),
+            &settings,
+        );
+    }
+}
+
+fn configure_rapier(config: &mut RapierConfiguration, settings: &PhysicsSettings) {
+    config.gravity = settings.gravity;
+    config.timestep_mode = TimestepMode::Fixed {
+        dt: FIXED_TIMESTEP.as_secs_f32(),
+        substeps: settings.substeps,
+    };
+}
+
+fn apply_settings(
+    settings: Res<PhysicsSettings>,
+    mut config: ResMut<RapierConfiguration>,
+    mut controllers: Query<&mut KinematicCharacterController>,
+) {
+    configure_rapier(&mut config, &settings);
+    for mut controller in &mut controllers {
+        controller.offset = CharacterLength::Absolute(settings.character_offset);
+    }
+}
+
+// Puts bodies back where the last physics step left them. A `Transform` that was changed since
+// it was rendered has been moved on purpose, and is kept as the new position instead
+fn restore_transforms(mut query: Query<(&mut Transform, &mut PhysicsInterpolation)>) {
+    for (mut transform, mut interpolation) in &mut query {
+        if transform.translation == interpolation.rendered {
+            if transform.translation != interpolation.current {
+                transform.translation = interpolation.current;
+            }
+        } else {
+            interpolation.previous = transform.translation;
+            interpolation.current = transform.translation;
+        }
+        interpolation.rendered = transform.translation;
+    }
+}
+
+fn store_transforms(mut query: Query<(&Transform, &mut PhysicsInterpolation)>) {
+    for (transform, mut interpolation) in &mut query {
+        interpolation.previous = interpolation.current;
+        interpolation.current = transform.translation;
+        interpolation.rendered = transform.translation;
+    }
+}
+
+// Bodies spawned since the last frame start from where they were placed
+fn init_interpolation(
+    mut query: Query<(&Transform, &mut PhysicsInterpolation), Added<PhysicsInterpolation>>,
+) {
+    for (transform, mut interpolation) in &mut query {
+        interpolation.previous = transform.translation;
+        interpolation.current = transform.translation;
+        interpolation.rendered = transform.translation;
+    }
+}
+
+fn interpolate_transforms(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &mut PhysicsInterpolation)>,
+) {
+    let alpha = fixed_time.overstep_percentage();
+    for (mut transform, mut interpolation) in &mut query {
+        let translation = interpolation.previous.lerp(interpolation.current, alpha);
+        transform.translation = translation;
+        interpolation.rendered = translation;
+    }
+}
@@ -3,18 +3,25 @@ use bevy_rapier2d::prelude::*;
 
 use std::time::Duration;
 
+use crate::animator::{Animation, AnimationPlugin};
 use crate::consts;
-use game::animator::{Animation, AnimationPlugin};
+use crate::environment::{LevelSeed, NextLevelSeed, Zombie};
+use crate::events::{PlayerHitByEnemy, PlayerReachedFlag};
+use crate::input::{Action, ActionDevices, PlayerInput, PlayerInputPlugin, PlayerStick};
+use crate::level::PLAYER_START;
+use crate::loading::GameAssets;
+use crate::logging::LEVEL;
+use crate::physics::{GameplaySet, Layer, PhysicsInterpolation, PhysicsSettings};
 
-use super::{despawn_screen, GameState, GameTimer, OnGameScreen, PlayerFlag, WinFlag};
+use crate::{despawn_screen, GameState, GameTimer, OnGameScreen, PlayerFlag, TEXT_COLOR};
 
-const P_WALK_U: &[usize] = &[0, 1, 2, 3, 4, 5, 6, 7, 8];
-const P_WALK_L: &[usize] = &[9, 10, 11, 12, 13, 14, 15, 16, 17];
-const P_WALK_D: &[usize] = &[18, 19, 20, 21, 22, 23, 24, 25, 26];
-const P_WALK_R: &[usize] = &[27, 28, 29, 30, 31, 32, 33, 34, 35];
-const P_IDLE: usize = 18;
+pub const P_WALK_U: &[usize] = &[0, 1, 2, 3, 4, 5, 6, 7, 8];
+pub const P_WALK_L: &[usize] = &[9, 10, 11, 12, 13, 14, 15, 16, 17];
+pub const P_WALK_D: &[usize] = &[18, 19, 20, 21, 22, 23, 24, 25, 26];
+pub const P_WALK_R: &[usize] = &[27, 28, 29, 30, 31, 32, 33, 34, 35];
+pub const P_IDLE: usize = 18;
 
-const CYCLE_DELAY: Duration = Duration::from_millis(100);
+pub const CYCLE_DELAY: Duration = Duration::from_millis(100);
 
 #[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
 pub enum PlayerStatus {
@@ -22,35 +29,167 @@ pub enum PlayerStatus {
     Paused,
 }
 
+// How the player gets up to speed and slows down, it can be changed at any time
+#[derive(Resource, Debug, PartialEq, Clone, Copy)]
+pub struct PlayerMovement {
+    // In pixels per second, whatever the direction
+    pub max_speed: f32,
+    // In pixels per second squared, while a direction is held
+    pub acceleration: f32,
+    // In pixels per second squared, once nothing is held
+    pub deceleration: f32,
+}
+
+impl Default for PlayerMovement {
+    fn default() -> Self {
+        Self {
+            max_speed: consts::PLAYER_VELOCITY_X,
+            acceleration: consts::PLAYER_ACCELERATION,
+            deceleration: consts::PLAYER_DECELERATION,
+        }
+    }
+}
+
+// Velocity the player moves at, in pixels per second. The character controller is moved by hand,
+// so this is kept apart from Rapier's `Velocity`
+#[derive(Component, Debug, Default, PartialEq, Clone, Copy, Deref, DerefMut)]
+pub struct PlayerVelocity(pub Vec2);
+
+// Tuning of the dash, it can be changed at any time
+#[derive(Resource, Debug, PartialEq, Clone, Copy)]
+pub struct DashSettings {
+    // In pixels per second
+    pub speed: f32,
+    // In seconds, how long the dash lasts
+    pub duration: f32,
+    // In seconds from the start of a dash to when the next one can be done
+    pub cooldown: f32,
+    // In seconds from the start of a dash, zombies can't end the run meanwhile
+    pub invulnerability: f32,
+}
+
+impl Default for DashSettings {
+    fn default() -> Self {
+        Self {
+            speed: consts::DASH_SPEED,
+            duration: consts::DASH_DURATION,
+            cooldown: consts::DASH_COOLDOWN,
+            invulnerability: consts::DASH_INVULNERABILITY,
+        }
+    }
+}
+
+// Direction the player last asked to move in, which is where a dash goes
+#[derive(Component, Debug, PartialEq, Clone, Copy, Deref)]
+pub struct Facing(pub Vec2);
+
+impl Default for Facing {
+    // The idle sprite looks down
+    fn default() -> Self {
+        Self(Vec2::NEG_Y)
+    }
+}
+
+// Dash of the player. Each countdown is in seconds and over once it reaches 0
+#[derive(Component, Debug, Default, PartialEq, Clone, Copy)]
+pub struct Dash {
+    direction: Vec2,
+    moving: f32,
+    cooldown: f32,
+    invulnerable: f32,
+    // The input is read once per frame while there can be any number of steps per frame, so a
+    // press is told apart from the previous step rather than with `just_pressed`
+    held: bool,
+}
+
+impl Dash {
+    pub fn is_dashing(&self) -> bool {
+        self.moving > 0.0
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable > 0.0
+    }
+
+    // From 0.0 right as a dash starts to 1.0 once the next one can be done
+    pub fn readiness(&self, settings: &DashSettings) -> f32 {
+        if settings.cooldown <= 0.0 {
+            return 1.0;
+        }
+        (1.0 - self.cooldown / settings.cooldown).clamp(0.0, 1.0)
+    }
+}
+
+// Cooldown bar of the dash in the HUD
+#[derive(Component)]
+struct DashMeter;
+
+// While enabled, zombies no longer end the run
+#[derive(Resource, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct GodMode(pub bool);
+
+// Set once the developer console was used during the current run. Such a run can't be
+// reproduced from its input, so it is neither saved as a replay nor as a best time
+#[derive(Resource, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct RunCheated(pub bool);
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Game), setup_player)
+        app.add_systems(OnEnter(GameState::Game), (setup_player, spawn_dash_meter))
             .insert_resource(PlayerStatus::Active)
-            .add_systems(Update, movement.run_if(in_state(GameState::Game)))
+            .init_resource::<GodMode>()
+            .init_resource::<RunCheated>()
+            .add_systems(OnEnter(GameState::Game), reset_cheats)
+            .add_systems(
+                FixedUpdate,
+                hit_by_lingering_enemies
+                    .in_set(GameplaySet::Events)
+                    .run_if(in_state(GameState::Game)),
+            )
+            .init_resource::<PlayerMovement>()
+            .init_resource::<DashSettings>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    (dash, movement).chain(),
+                    (announce_outcome, end_run).chain(),
+                    exit_game,
+                )
+                    .in_set(GameplaySet::Logic)
+                    .run_if(in_state(GameState::Game)),
+            )
+            .add_systems(
+                Update,
+                (show_dash_meter, show_invulnerability).run_if(in_state(GameState::Game)),
+            )
             .add_systems(Update, anim_idle.run_if(in_state(GameState::Game)))
             .add_systems(Update, anim_mov_d.run_if(in_state(GameState::Game)))
             .add_systems(Update, anim_mov_l.run_if(in_state(GameState::Game)))
             .add_systems(Update, anim_mov_r.run_if(in_state(GameState::Game)))
             .add_systems(Update, anim_mov_u.run_if(in_state(GameState::Game)))
-            .add_systems(Update, display_events.run_if(in_state(GameState::Game)))
             .insert_resource(GameTimer(Timer::from_seconds(3.0, TimerMode::Once)))
-            .add_systems(Update, exit_game.run_if(in_state(GameState::Game)))
             .add_systems(Update, hot_keys.run_if(in_state(GameState::Game)))
+            .add_systems(
+                Update,
+                restart_level
+                    .run_if(in_state(GameState::Game))
+                    .run_if(restart_pressed),
+            )
             .add_systems(OnExit(GameState::Game), despawn_screen::<OnGameScreen>)
-            .add_plugins(AnimationPlugin);
+            .add_plugins(AnimationPlugin)
+            .add_plugins(PlayerInputPlugin);
     }
 }
 
-pub fn setup_player(
-    mut commands: Commands,
-    mut atlases: ResMut<Assets<TextureAtlas>>,
-    server: Res<AssetServer>,
-) {
-    let image_handle: Handle<Image> = server.load("texture/player.png");
+// Cuts the player sprite sheet into the frames used by the walking animations
+pub fn player_atlas(
+    atlases: &mut Assets<TextureAtlas>,
+    assets: &GameAssets,
+) -> Handle<TextureAtlas> {
     let texture_atlas = TextureAtlas::from_grid(
-        image_handle,
+        assets.player_texture.clone(),
         Vec2::new(consts::SPRTPL_W, consts::SPRTPL_H),
         consts::SPRTPL_COLS,
         consts::SPRTPL_ROWS,
@@ -58,28 +197,45 @@ pub fn setup_player(
         None,
     );
 
-    let atlas_handle = atlases.add(texture_atlas);
+    atlases.add(texture_atlas)
+}
+
+pub fn setup_player(
+    mut commands: Commands,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+    assets: Res<GameAssets>,
+    physics: Res<PhysicsSettings>,
+) {
+    let atlas_handle = player_atlas(&mut atlases, &assets);
 
     commands
         .spawn((
             SpriteSheetBundle {
-                sprite: TextureAtlasSprite::new(18),
+                sprite: TextureAtlasSprite::new(P_IDLE),
                 texture_atlas: atlas_handle,
                 transform: Transform {
-                    translation: Vec3::new(
-                        consts::WINDOW_LEFT_X + 730.0,
-                        consts::WINDOW_BOTTOM_Y + 50.0,
-                        0.0,
-                    ),
+                    translation: PLAYER_START.extend(0.0),
                     ..Default::default()
                 },
                 ..Default::default()
             },
             OnGameScreen,
             PlayerFlag,
+            PlayerVelocity::default(),
+            Facing::default(),
+            Dash::default(),
+            PhysicsInterpolation::default(),
         ))
         .insert(RigidBody::KinematicVelocityBased)
         .insert(ActiveEvents::COLLISION_EVENTS)
+        // Zombies are kinematic as well and the flag is fixed, contacts with them are only
+        // reported when explicitly enabled
+        .insert(
+            ActiveCollisionTypes::default()
+                | ActiveCollisionTypes::KINEMATIC_KINEMATIC
+                | ActiveCollisionTypes::KINEMATIC_STATIC,
+        )
+        .insert(Layer::Player.groups())
         .insert(Velocity {
             linvel: Vec2::new(0., 0.),
             angvel: 0.,
@@ -88,74 +244,132 @@ pub fn setup_player(
             consts::SPRTPL_W / 2.,
             consts::SPRTPL_H / 2.,
         ))
-        .insert(KinematicCharacterController::default());
+        .insert(physics.character_controller());
 }
 
-fn display_events(
-    mut collision_events: EventReader<CollisionEvent>,
-    query: Query<Entity, With<WinFlag>>,
-    asset_server: Res<AssetServer>,
-    mut commands: Commands,
-    // mut game_state: ResMut<NextState<GameState>>,
-    mut pstatus: ResMut<PlayerStatus>,
-) {
-    for collision_event in collision_events.read() {
-        match collision_event {
-            CollisionEvent::Started(entity1, entity2, _flags) => {
-                if let Ok(component) = query.get(*entity1) {
-                    println!("Started: Entity1's component = {:?}", component);
-
-                    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
-                    let text_style = TextStyle {
-                        font: font.clone(),
-                        font_size: 100.0,
-                        color: Color::WHITE,
-                    };
-                    let text_alignment = TextAlignment::Center;
-                    // Demonstrate changing translation
-                    commands.spawn((
-                        Text2dBundle {
-                            text: Text::from_section("You WIN!", text_style.clone())
-                                .with_alignment(text_alignment),
-                            ..default()
-                        },
-                        OnGameScreen,
-                    ));
+// God mode stays on from one run to the next, which taints the new run from the start
+fn reset_cheats(god_mode: Res<GodMode>, mut cheated: ResMut<RunCheated>) {
+    cheated.0 = god_mode.0;
+}
 
-                    *pstatus = PlayerStatus::Paused;
-                    commands.insert_resource(GameTimer(Timer::from_seconds(3.0, TimerMode::Once)));
-                }
-                if let Ok(component) = query.get(*entity2) {
-                    println!("Started: Entity2's component = {:?}", component);
-
-                    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
-                    let text_style = TextStyle {
-                        font: font.clone(),
-                        font_size: 100.0,
-                        color: Color::WHITE,
-                    };
-                    let text_alignment = TextAlignment::Center;
-                    // Demonstrate changing translation
-                    commands.spawn((
-                        Text2dBundle {
-                            text: Text::from_section("You WIN!", text_style.clone())
-                                .with_alignment(text_alignment),
-                            ..default()
-                        },
-                        OnGameScreen,
-                    ));
+// How a run ended, reaching the flag wins even if a zombie got the player on the same step
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum RunOutcome {
+    Won,
+    Lost,
+}
 
-                    *pstatus = PlayerStatus::Paused;
-                    commands.insert_resource(GameTimer(Timer::from_seconds(3.0, TimerMode::Once)));
-                }
-            }
-            CollisionEvent::Stopped(entity1, entity2, _flags) => {
-                println!("Stopped: Entity1 = {:?}, Entity2 = {:?}", entity1, entity2);
+fn run_outcome(
+    reached_flag: &mut EventReader<PlayerReachedFlag>,
+    hit_by_enemy: &mut EventReader<PlayerHitByEnemy>,
+    immune: bool,
+) -> Option<RunOutcome> {
+    let won = reached_flag.read().count() > 0;
+    let lost = hit_by_enemy.read().count() > 0 && !immune;
+    if won {
+        Some(RunOutcome::Won)
+    } else if lost {
+        Some(RunOutcome::Lost)
+    } else {
+        None
+    }
+}
+
+// Zombies don't end the run in god mode, or right after a dash
+pub fn is_immune(god_mode: &GodMode, dashes: &Query<&Dash>) -> bool {
+    god_mode.0 || dashes.iter().any(Dash::is_invulnerable)
+}
+
+// A zombie only hits the player when their contact starts, so the ones still touching the player
+// when it stops being immune hit it again
+fn hit_by_lingering_enemies(
+    god_mode: Res<GodMode>,
+    dashes: Query<&Dash>,
+    players: Query<Entity, With<PlayerFlag>>,
+    zombies: Query<(), With<Zombie>>,
+    rapier_context: Res<RapierContext>,
+    mut was_immune: Local<bool>,
+    mut hit_by_enemy: EventWriter<PlayerHitByEnemy>,
+) {
+    let immune = is_immune(&god_mode, &dashes);
+    let immunity_ended = *was_immune && !immune;
+    *was_immune = immune;
+    if !immunity_ended {
+        return;
+    }
+
+    for player in &players {
+        for contact in rapier_context.contacts_with(player) {
+            let other = if contact.collider1() == player {
+                contact.collider2()
+            } else {
+                contact.collider1()
+            };
+            if contact.has_any_active_contacts() && zombies.contains(other) {
+                hit_by_enemy.send(PlayerHitByEnemy { enemy: other });
             }
         }
     }
 }
 
+// Shows how the run ended in the middle of the screen
+fn announce_outcome(
+    mut reached_flag: EventReader<PlayerReachedFlag>,
+    mut hit_by_enemy: EventReader<PlayerHitByEnemy>,
+    god_mode: Res<GodMode>,
+    dashes: Query<&Dash>,
+    pstatus: Res<PlayerStatus>,
+    assets: Res<GameAssets>,
+    mut commands: Commands,
+) {
+    let immune = is_immune(&god_mode, &dashes);
+    let Some(outcome) = run_outcome(&mut reached_flag, &mut hit_by_enemy, immune) else {
+        return;
+    };
+    if *pstatus != PlayerStatus::Active {
+        return;
+    }
+
+    let message = match outcome {
+        RunOutcome::Won => "You WIN!",
+        RunOutcome::Lost => "You LOSE!",
+    };
+    let text_style = TextStyle {
+        font: assets.font.clone(),
+        font_size: 100.0,
+        color: Color::WHITE,
+    };
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(message, text_style).with_alignment(TextAlignment::Center),
+            ..default()
+        },
+        OnGameScreen,
+    ));
+}
+
+// The player stops once the run is over, and goes back to the menu after a short while
+fn end_run(
+    mut reached_flag: EventReader<PlayerReachedFlag>,
+    mut hit_by_enemy: EventReader<PlayerHitByEnemy>,
+    god_mode: Res<GodMode>,
+    dashes: Query<&Dash>,
+    mut pstatus: ResMut<PlayerStatus>,
+    mut commands: Commands,
+) {
+    let immune = is_immune(&god_mode, &dashes);
+    let Some(outcome) = run_outcome(&mut reached_flag, &mut hit_by_enemy, immune) else {
+        return;
+    };
+    if *pstatus != PlayerStatus::Active {
+        return;
+    }
+
+    info!(target: LEVEL, "Run over: {outcome:?}");
+    *pstatus = PlayerStatus::Paused;
+    commands.insert_resource(GameTimer(Timer::from_seconds(3.0, TimerMode::Once)));
+}
+
 fn exit_game(
     time: Res<Time>,
     mut game_state: ResMut<NextState<GameState>>,
@@ -165,53 +379,228 @@ fn exit_game(
 ) {
     if *pstatus == PlayerStatus::Paused && timer.tick(time.delta()).finished() {
         let mut player = query.single_mut();
-        player.translation = Some(Vec2::new(
-            consts::WINDOW_LEFT_X + 730.0,
-            consts::WINDOW_BOTTOM_Y + 50.0,
-        ));
+        player.translation = Some(PLAYER_START);
         *pstatus = PlayerStatus::Active;
         game_state.set(GameState::Menu);
     }
 }
 
-fn hot_keys(input: Res<Input<KeyCode>>, mut game_state: ResMut<NextState<GameState>>) {
-    if input.just_pressed(KeyCode::Escape) {
+// Pausing and restarting are read from the devices, so that they also work while a replay plays
+fn hot_keys(devices: ActionDevices, mut game_state: ResMut<NextState<GameState>>) {
+    if devices.just_pressed(Action::Pause) {
         game_state.set(GameState::Menu);
     }
 }
 
+fn restart_pressed(devices: ActionDevices) -> bool {
+    devices.just_pressed(Action::Restart)
+}
+
+// Plays the same level again from the start, without going through the menu. The game is torn
+// down and set up again as if it was left and entered
+fn restart_level(world: &mut World) {
+    let seed = world.resource::<LevelSeed>().0;
+    info!(target: LEVEL, "Restarting level {seed}");
+    world.run_schedule(OnExit(GameState::Game));
+    world.insert_resource(NextLevelSeed(Some(seed)));
+    world.insert_resource(PlayerStatus::Active);
+    world.run_schedule(OnEnter(GameState::Game));
+}
+
+// Direction the player asks to move in, at most 1 long. The stick takes over the keys, its tilt
+// setting the speed, while the keys always ask for full speed even diagonally
+pub fn wished_direction(input: &Input<Action>, stick: Vec2) -> Vec2 {
+    if stick != Vec2::ZERO {
+        return stick.clamp_length_max(1.0);
+    }
+
+    let mut direction = Vec2::ZERO;
+    if input.pressed(Action::MoveRight) {
+        direction.x += 1.0;
+    }
+    if input.pressed(Action::MoveLeft) {
+        direction.x -= 1.0;
+    }
+    if input.pressed(Action::MoveUp) {
+        direction.y += 1.0;
+    }
+    if input.pressed(Action::MoveDown) {
+        direction.y -= 1.0;
+    }
+    direction.normalize_or_zero()
+}
+
+// Brings `velocity` closer to `target` by at most `max_change`
+pub fn approach(velocity: Vec2, target: Vec2, max_change: f32) -> Vec2 {
+    velocity + (target - velocity).clamp_length_max(max_change)
+}
+
+// Starts a dash on a new press of the action once the previous one cooled down, and counts
+// down the current one
+fn dash(
+    input: Res<PlayerInput>,
+    settings: Res<DashSettings>,
+    time: Res<Time>,
+    pstatus: Res<PlayerStatus>,
+    mut query: Query<(&mut Dash, &Facing)>,
+) {
+    let (mut dash, facing) = query.single_mut();
+
+    let delta = time.delta_seconds();
+    dash.moving = (dash.moving - delta).max(0.0);
+    dash.cooldown = (dash.cooldown - delta).max(0.0);
+    dash.invulnerable = (dash.invulnerable - delta).max(0.0);
+
+    let held = input.pressed(Action::Dash);
+    let pressed = held && !dash.held;
+    dash.held = held;
+
+    if *pstatus != PlayerStatus::Active {
+        dash.moving = 0.0;
+    } else if pressed && dash.cooldown == 0.0 {
+        *dash = Dash {
+            direction: facing.0,
+            moving: settings.duration,
+            cooldown: settings.cooldown,
+            invulnerable: settings.invulnerability,
+            held,
+        };
+    }
+}
+
 pub fn movement(
-    input: Res<Input<KeyCode>>,
+    input: Res<PlayerInput>,
+    stick: Res<PlayerStick>,
+    settings: Res<PlayerMovement>,
+    dash_settings: Res<DashSettings>,
     time: Res<Time>,
-    mut query: Query<&mut KinematicCharacterController>,
+    mut query: Query<(
+        &mut KinematicCharacterController,
+        &mut PlayerVelocity,
+        &mut Facing,
+        &Dash,
+    )>,
     pstatus: Res<PlayerStatus>,
 ) {
-    let mut player = query.single_mut();
+    let (mut player, mut velocity, mut facing, dash) = query.single_mut();
 
-    let mut translation = Vec2::new(0.0, 0.0);
+    if *pstatus != PlayerStatus::Active {
+        velocity.0 = Vec2::ZERO;
+        return;
+    }
 
-    //move right
-    if *pstatus == PlayerStatus::Active {
-        if input.pressed(KeyCode::D) {
-            translation.x += time.delta_seconds() * consts::PLAYER_VELOCITY_X;
-        }
+    let direction = wished_direction(&input, stick.0);
+    if direction != Vec2::ZERO {
+        facing.0 = direction.normalize();
+    }
 
-        //move left
-        if input.pressed(KeyCode::A) {
-            translation.x -= time.delta_seconds() * consts::PLAYER_VELOCITY_X;
-        }
+    if dash.is_dashing() {
+        velocity.0 = dash.direction * dash_settings.speed;
+    } else {
+        let target = direction * settings.max_speed;
+        // Turning around counts as accelerating, only letting go of every direction slows down
+        let rate = if target == Vec2::ZERO {
+            settings.deceleration
+        } else {
+            settings.acceleration
+        };
+        // Coming out of a dash goes straight back to the walking speed
+        let current = velocity.0.clamp_length_max(settings.max_speed);
+        velocity.0 = approach(current, target, rate * time.delta_seconds());
+    }
 
-        //move up
-        if input.pressed(KeyCode::W) {
-            translation.y += time.delta_seconds() * consts::PLAYER_VELOCITY_X;
-        }
+    player.translation = Some(velocity.0 * time.delta_seconds());
+}
 
-        //move down
-        if input.pressed(KeyCode::S) {
-            translation.y -= time.delta_seconds() * consts::PLAYER_VELOCITY_X;
+fn spawn_dash_meter(mut commands: Commands, assets: Res<GameAssets>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "Dash",
+                    TextStyle {
+                        font: assets.font.clone(),
+                        font_size: 20.0,
+                        color: TEXT_COLOR,
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::right(Val::Px(10.0)),
+                    ..default()
+                }),
+            );
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(100.0),
+                        height: Val::Px(10.0),
+                        ..default()
+                    },
+                    background_color: Color::rgba(1.0, 1.0, 1.0, 0.2).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        DashMeter,
+                    ));
+                });
+        });
+}
+
+// The bar fills up as the dash cools down, and lights up once it can be done again
+fn show_dash_meter(
+    settings: Res<DashSettings>,
+    dashes: Query<&Dash>,
+    mut meters: Query<(&mut Style, &mut BackgroundColor), With<DashMeter>>,
+) {
+    let Ok(dash) = dashes.get_single() else {
+        return;
+    };
+    let readiness = dash.readiness(&settings);
+    let color = if readiness < 1.0 {
+        Color::GRAY
+    } else {
+        TEXT_COLOR
+    };
+    for (mut style, mut background) in &mut meters {
+        let width = Val::Percent(readiness * 100.0);
+        if style.width != width {
+            style.width = width;
+        }
+        if background.0 != color {
+            background.0 = color;
         }
+    }
+}
 
-        player.translation = Some(translation);
+// The player is see-through while zombies can't get it
+fn show_invulnerability(mut query: Query<(&Dash, &mut TextureAtlasSprite)>) {
+    for (dash, mut sprite) in &mut query {
+        let alpha = if dash.is_invulnerable() { 0.5 } else { 1.0 };
+        if sprite.color.a() != alpha {
+            sprite.color.set_a(alpha);
+        }
     }
 }
 
@@ -0,0 +1,266 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    environment::{LevelSeed, NextLevelSeed},
+    input::{
+        dequantize_stick, quantize_stick, Action, InputScript, InputSource, PlayerInput,
+        PlayerStick,
+    },
+    logging::REPLAY,
+    physics::GameplaySet,
+    player::RunCheated,
+    GameState,
+};
+
+// Actions that are stored in a replay, in the order of their bit in `ReplayFrame::keys`
+pub const RECORDED_ACTIONS: [Action; 5] = [
+    Action::MoveUp,
+    Action::MoveLeft,
+    Action::MoveDown,
+    Action::MoveRight,
+    Action::Dash,
+];
+
+// Where the last played run is saved
+pub const LAST_REPLAY_PATH: &str = "replays/last.replay";
+
+// Goes up whenever what a frame means changes, so that older replays are rejected instead of being
+// misread
+const REPLAY_HEADER: &str = "passive-power-replay 4";
+
+// Input of a single fixed timestep frame of a run
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ReplayFrame {
+    pub keys: u8,
+    // Tilt of the stick, as a number of `input::STICK_STEPS` on each axis
+    pub stick: IVec2,
+}
+
+// Everything needed to reproduce a run: the level seed and the input of every frame played on it
+#[derive(Resource, Debug, Default, PartialEq, Eq, Clone)]
+pub struct Replay {
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_string())
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut lines = text.lines();
+        if lines.next() != Some(REPLAY_HEADER) {
+            return Err(invalid("not a replay file"));
+        }
+        let seed = lines
+            .next()
+            .and_then(|line| line.strip_prefix("seed "))
+            .and_then(|seed| seed.parse().ok())
+            .ok_or_else(|| invalid("missing seed"))?;
+
+        let mut frames = Vec::new();
+        // A frame is its keys, followed by the stick when it is not at rest
+        for line in lines.filter(|line| !line.is_empty()) {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let frame = match words[..] {
+                [keys] => keys.parse().ok().map(|keys| ReplayFrame {
+                    keys,
+                    stick: IVec2::ZERO,
+                }),
+                [keys, x, y] => keys
+                    .parse()
+                    .ok()
+                    .zip(x.parse().ok().zip(y.parse().ok()))
+                    .map(|(keys, (x, y))| ReplayFrame {
+                        keys,
+                        stick: IVec2::new(x, y),
+                    }),
+                _ => None,
+            };
+            frames.push(frame.ok_or_else(|| invalid("bad frame input"))?);
+        }
+
+        Ok(Self { seed, frames })
+    }
+
+    // Converts the recorded keys into a script that can be fed to the player
+    pub fn to_script(&self) -> InputScript {
+        let mut script = InputScript::new();
+        let mut previous = 0;
+        for (frame, replay_frame) in self.frames.iter().enumerate() {
+            for (bit, action) in RECORDED_ACTIONS.iter().enumerate() {
+                let mask = 1 << bit;
+                match (previous & mask != 0, replay_frame.keys & mask != 0) {
+                    (false, true) => script = script.press(frame as u32, *action),
+                    (true, false) => script = script.release(frame as u32, *action),
+                    _ => {}
+                }
+            }
+            previous = replay_frame.keys;
+        }
+        script
+    }
+}
+
+impl std::fmt::Display for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{REPLAY_HEADER}")?;
+        writeln!(f, "seed {}", self.seed)?;
+        for frame in &self.frames {
+            if frame.stick == IVec2::ZERO {
+                writeln!(f, "{}", frame.keys)?;
+            } else {
+                writeln!(f, "{} {} {}", frame.keys, frame.stick.x, frame.stick.y)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Whether the current run is being recorded or is a replay being watched
+#[derive(Resource, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ReplayMode {
+    #[default]
+    Recording,
+    Playback,
+}
+
+// Frames recorded so far for the current run
+#[derive(Resource, Default, Deref, DerefMut)]
+struct Recorder(Vec<ReplayFrame>);
+
+// Index of the frame being played back
+#[derive(Resource, Default, Deref, DerefMut)]
+struct PlaybackFrame(usize);
+
+// Where replays are saved and loaded from
+#[derive(Resource, Debug, Clone)]
+pub struct ReplayPath(pub PathBuf);
+
+impl Default for ReplayPath {
+    fn default() -> Self {
+        Self(PathBuf::from(LAST_REPLAY_PATH))
+    }
+}
+
+// This plugin records every run to a replay file and can play a replay back frame by frame
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayMode>()
+            .init_resource::<ReplayPath>()
+            .init_resource::<Recorder>()
+            .init_resource::<PlaybackFrame>()
+            .add_systems(OnEnter(GameState::Game), start_run)
+            .add_systems(
+                FixedUpdate,
+                record_frame
+                    .in_set(GameplaySet::Logic)
+                    .run_if(in_state(GameState::Game))
+                    .run_if(resource_equals(ReplayMode::Recording)),
+            )
+            .add_systems(
+                FixedUpdate,
+                play_stick
+                    .in_set(GameplaySet::Input)
+                    .run_if(in_state(GameState::Game))
+                    .run_if(resource_equals(ReplayMode::Playback)),
+            )
+            .add_systems(
+                FixedUpdate,
+                advance_playback
+                    .in_set(GameplaySet::Logic)
+                    .run_if(in_state(GameState::Game))
+                    .run_if(resource_equals(ReplayMode::Playback)),
+            )
+            .add_systems(
+                OnExit(GameState::Game),
+                (
+                    save_recording
+                        .run_if(resource_equals(ReplayMode::Recording))
+                        .run_if(resource_equals(RunCheated(false))),
+                    stop_playback.run_if(resource_equals(ReplayMode::Playback)),
+                )
+                    .chain(),
+            );
+    }
+}
+
+// Sets everything up so that the next game reproduces `replay`, the caller is then expected to
+// switch to `GameState::Game`
+pub fn watch(commands: &mut Commands, replay: Replay) {
+    commands.insert_resource(NextLevelSeed(Some(replay.seed)));
+    commands.insert_resource(replay.to_script());
+    commands.insert_resource(InputSource::Script);
+    commands.insert_resource(ReplayMode::Playback);
+    commands.insert_resource(replay);
+}
+
+fn start_run(mut recorder: ResMut<Recorder>, mut playback: ResMut<PlaybackFrame>) {
+    recorder.clear();
+    **playback = 0;
+}
+
+fn record_frame(input: Res<PlayerInput>, stick: Res<PlayerStick>, mut recorder: ResMut<Recorder>) {
+    let keys = RECORDED_ACTIONS
+        .iter()
+        .enumerate()
+        .filter(|(_, action)| input.pressed(**action))
+        .fold(0, |keys, (bit, _)| keys | 1 << bit);
+    recorder.push(ReplayFrame {
+        keys,
+        stick: quantize_stick(stick.0),
+    });
+}
+
+// The stick is not part of the script, it is set straight from the frame being played back
+fn play_stick(replay: Res<Replay>, playback: Res<PlaybackFrame>, mut stick: ResMut<PlayerStick>) {
+    let steps = replay
+        .frames
+        .get(**playback)
+        .map_or(IVec2::ZERO, |frame| frame.stick);
+    stick.set_if_neq(PlayerStick(dequantize_stick(steps)));
+}
+
+fn save_recording(mut recorder: ResMut<Recorder>, seed: Res<LevelSeed>, path: Res<ReplayPath>) {
+    let replay = Replay {
+        seed: seed.0,
+        frames: std::mem::take(&mut **recorder),
+    };
+    if let Err(err) = replay.save(&path.0) {
+        error!(target: REPLAY, "Could not save replay to {}: {err}", path.0.display());
+    }
+}
+
+fn advance_playback(
+    replay: Res<Replay>,
+    mut playback: ResMut<PlaybackFrame>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    **playback += 1;
+    if **playback >= replay.frames.len() {
+        game_state.set(GameState::Menu);
+    }
+}
+
+fn stop_playback(mut commands: Commands) {
+    commands.insert_resource(InputSource::Keyboard);
+    commands.insert_resource(ReplayMode::Recording);
+    commands.remove_resource::<Replay>();
+}
@@ -0,0 +1,267 @@
+use std::{fs, io, path::Path};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    input::{input_name, parse_input, Action, Bindings},
+    logging::SETTINGS,
+    DisplayQuality, GameState, MasterVolume, MusicVolume, Muted, SfxVolume, UiVolume, Volume,
+};
+
+// Where the settings are saved between runs
+pub const SETTINGS_PATH: &str = "settings/passive-power.settings";
+
+const SETTINGS_HEADER: &str = "passive-power-settings 1";
+
+// Everything that can be set through the menus
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Settings {
+    pub display_quality: DisplayQuality,
+    pub master: Volume,
+    pub music: Volume,
+    pub sfx: Volume,
+    pub ui: Volume,
+    pub muted: bool,
+    pub bindings: Bindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            display_quality: DisplayQuality::Medium,
+            master: Volume(9),
+            music: Volume(7),
+            sfx: Volume(7),
+            ui: Volume(7),
+            muted: false,
+            bindings: Bindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_string())
+    }
+
+    // One setting per line: `display low|medium|high`, `master|music|effects|interface 0-10`,
+    // `muted yes|no`, `key <action> <key>` or `button <action> <button>`. Settings left out of the
+    // file, or that can't be read, keep their default value
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut lines = text.lines();
+        if lines.next() != Some(SETTINGS_HEADER) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a settings file",
+            ));
+        }
+
+        let mut settings = Self::default();
+        for line in lines.map(str::trim).filter(|line| !line.is_empty()) {
+            if let Err(message) = settings.parse_line(line) {
+                warn!(target: SETTINGS, "Skipping a setting: {message}: {line}");
+            }
+        }
+        Ok(settings)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), &'static str> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let volume = |value: &str| match value.parse() {
+            Ok(value) if value <= Volume::MAX => Ok(Volume(value)),
+            _ => Err("bad volume"),
+        };
+        let action = |name: &str| Action::from_name(name).ok_or("unknown action");
+        match words[..] {
+            ["display", quality] => {
+                self.display_quality = match quality {
+                    "low" => DisplayQuality::Low,
+                    "medium" => DisplayQuality::Medium,
+                    "high" => DisplayQuality::High,
+                    _ => return Err("bad display quality"),
+                }
+            }
+            ["master", value] => self.master = volume(value)?,
+            ["music", value] => self.music = volume(value)?,
+            ["effects", value] => self.sfx = volume(value)?,
+            ["interface", value] => self.ui = volume(value)?,
+            ["muted", "yes"] => self.muted = true,
+            ["muted", "no"] => self.muted = false,
+            ["key", name, key] => {
+                let key = parse_input(key).ok_or("bad key")?;
+                self.bindings.bind_key(action(name)?, key);
+            }
+            ["button", name, button] => {
+                let button = parse_input(button).ok_or("bad button")?;
+                self.bindings.bind_button(action(name)?, button);
+            }
+            _ => return Err("malformed line"),
+        }
+        Ok(())
+    }
+
+    // Takes the settings that went from `before` to `after`, leaving the others as they are
+    pub fn take_changes(&mut self, before: &Settings, after: &Settings) {
+        if before.display_quality != after.display_quality {
+            self.display_quality = after.display_quality;
+        }
+        if before.master != after.master {
+            self.master = after.master;
+        }
+        if before.music != after.music {
+            self.music = after.music;
+        }
+        if before.sfx != after.sfx {
+            self.sfx = after.sfx;
+        }
+        if before.ui != after.ui {
+            self.ui = after.ui;
+        }
+        if before.muted != after.muted {
+            self.muted = after.muted;
+        }
+        if before.bindings != after.bindings {
+            self.bindings = after.bindings.clone();
+        }
+    }
+
+    fn insert_into(self, app: &mut App) {
+        app.insert_resource(self.display_quality)
+            .insert_resource(MasterVolume(self.master))
+            .insert_resource(MusicVolume(self.music))
+            .insert_resource(SfxVolume(self.sfx))
+            .insert_resource(UiVolume(self.ui))
+            .insert_resource(Muted(self.muted))
+            .insert_resource(self.bindings);
+    }
+}
+
+impl std::fmt::Display for Settings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{SETTINGS_HEADER}")?;
+        let quality = match self.display_quality {
+            DisplayQuality::Low => "low",
+            DisplayQuality::Medium => "medium",
+            DisplayQuality::High => "high",
+        };
+        writeln!(f, "display {quality}")?;
+        writeln!(f, "master {}", self.master.0)?;
+        writeln!(f, "music {}", self.music.0)?;
+        writeln!(f, "effects {}", self.sfx.0)?;
+        writeln!(f, "interface {}", self.ui.0)?;
+        writeln!(f, "muted {}", if self.muted { "yes" } else { "no" })?;
+        for action in Action::ALL {
+            if let Some(key) = self.bindings.key(action) {
+                writeln!(f, "key {} {}", action.name(), input_name(key))?;
+            }
+            if let Some(button) = self.bindings.button(action) {
+                writeln!(f, "button {} {}", action.name(), input_name(button))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The settings as currently set in the app
+#[derive(SystemParam)]
+struct CurrentSettings<'w> {
+    display_quality: Res<'w, DisplayQuality>,
+    master: Res<'w, MasterVolume>,
+    music: Res<'w, MusicVolume>,
+    sfx: Res<'w, SfxVolume>,
+    ui: Res<'w, UiVolume>,
+    muted: Res<'w, Muted>,
+    bindings: Res<'w, Bindings>,
+}
+
+impl CurrentSettings<'_> {
+    fn is_changed(&self) -> bool {
+        self.display_quality.is_changed()
+            || self.master.is_changed()
+            || self.music.is_changed()
+            || self.sfx.is_changed()
+            || self.ui.is_changed()
+            || self.muted.is_changed()
+            || self.bindings.is_changed()
+    }
+
+    fn to_settings(&self) -> Settings {
+        Settings {
+            display_quality: *self.display_quality,
+            master: **self.master,
+            music: **self.music,
+            sfx: **self.sfx,
+            ui: **self.ui,
+            muted: self.muted.0,
+            bindings: self.bindings.clone(),
+        }
+    }
+}
+
+// The settings as written in the settings file, and the settings of the app when they were last
+// compared to it. Only what changed in between is written back, so that the settings set on the
+// command line are never saved
+#[derive(Resource)]
+struct SavedSettings {
+    saved: Settings,
+    applied: Settings,
+}
+
+// This plugin sets the settings saved by the previous run up, and saves them again whenever they
+// are changed in the menus. Resources inserted after this plugin, such as the ones set on the
+// command line, take precedence
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = match Settings::load(SETTINGS_PATH) {
+            Ok(settings) => settings,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Settings::default(),
+            Err(err) => {
+                warn!(target: SETTINGS, "Could not load settings from {SETTINGS_PATH}, using the defaults: {err}");
+                Settings::default()
+            }
+        };
+        settings.clone().insert_into(app);
+        app.insert_resource(SavedSettings {
+            applied: settings.clone(),
+            saved: settings,
+        })
+        .add_systems(Startup, remember_applied_settings)
+        .add_systems(
+            Update,
+            save_settings
+                .run_if(in_state(GameState::Menu))
+                .run_if(settings_changed),
+        );
+    }
+}
+
+// Runs once every plugin and the command line have had their say
+fn remember_applied_settings(settings: CurrentSettings, mut saved: ResMut<SavedSettings>) {
+    saved.applied = settings.to_settings();
+}
+
+fn settings_changed(settings: CurrentSettings) -> bool {
+    settings.is_changed()
+}
+
+fn save_settings(settings: CurrentSettings, mut saved: ResMut<SavedSettings>) {
+    let current = settings.to_settings();
+    if current == saved.applied {
+        return;
+    }
+    let SavedSettings { saved, applied } = &mut *saved;
+    saved.take_changes(applied, &current);
+    *applied = current;
+    if let Err(err) = saved.save(SETTINGS_PATH) {
+        error!(target: SETTINGS, "Could not save settings to {SETTINGS_PATH}: {err}");
+    }
+}
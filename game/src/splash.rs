@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use super::{despawn_screen, GameState};
+use crate::{despawn_screen, loading::GameAssets, GameState};
 
 // This plugin will display a splash screen with Bevy logo for 1 second before switching to the menu
 pub struct SplashPlugin;
@@ -26,8 +26,8 @@ struct OnSplashScreen;
 #[derive(Resource, Deref, DerefMut)]
 struct SplashTimer(Timer);
 
-fn splash_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let icon = asset_server.load("branding/icon.png");
+fn splash_setup(mut commands: Commands, assets: Res<GameAssets>) {
+    let icon = assets.splash_icon.clone();
     // Display the logo
     commands
         .spawn((
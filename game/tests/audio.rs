@@ -0,0 +1,105 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+
+use game::audio::{self, MusicDirector, MusicFades, GAME_MUSIC, MENU_MUSIC, TRACKS};
+
+fn assets_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("assets")
+}
+
+// Every track can be played, from its preferred file or from one it falls back to
+#[test]
+fn referenced_audio_files_exist() {
+    for (name, candidates) in TRACKS {
+        assert!(
+            candidates
+                .iter()
+                .any(|path| assets_dir().join(path).is_file()),
+            "no file of the {name} exists (tried {})",
+            candidates.join(", ")
+        );
+    }
+}
+
+#[test]
+fn missing_track_falls_back() {
+    let dir = assets_dir();
+    assert_eq!(
+        audio::find_track("test", &["audios/missing.ogg", "audios/hit.wav"], &dir),
+        Some("audios/hit.wav")
+    );
+    assert_eq!(
+        audio::find_track("test", &["audios/missing.ogg"], &dir),
+        None
+    );
+}
+
+#[test]
+fn game_music_skips_a_missing_first_track() {
+    let dir = std::env::temp_dir().join("passive-power-game-music");
+    fs::create_dir_all(dir.join("audios")).unwrap();
+    let _ = fs::remove_file(dir.join(GAME_MUSIC[0]));
+    fs::write(dir.join(GAME_MUSIC[1]), []).unwrap();
+
+    assert_eq!(
+        audio::find_track("in-game music", GAME_MUSIC, &dir),
+        Some(GAME_MUSIC[1])
+    );
+}
+
+#[test]
+fn layers_follow_the_threat_of_zombies() {
+    assert_eq!(audio::threat(1000.0), 0.0);
+    assert_eq!(audio::threat(0.0), 1.0);
+
+    let close = audio::threat(150.0);
+    assert!(0.0 < close && close < 1.0);
+    assert_eq!(audio::layer_gain(0.25, 0.0, 0.5), 0.5);
+    assert_eq!(audio::layer_gain(0.25, 0.5, 1.0), 0.0);
+    assert_eq!(audio::layer_gain(1.0, 0.5, 1.0), 1.0);
+}
+
+#[test]
+fn emitted_sounds_are_placed_around_the_player() {
+    let (gain, panning) = audio::spatial_gain(Vec2::ZERO, Vec2::ZERO);
+    assert_eq!((gain, panning), (1.0, 0.5));
+
+    let (near, right) = audio::spatial_gain(Vec2::ZERO, Vec2::new(100.0, 0.0));
+    let (far, left) = audio::spatial_gain(Vec2::ZERO, Vec2::new(-400.0, 0.0));
+    assert!(far < near && near < 1.0);
+    assert_eq!((right, left), (1.0, 0.0));
+    assert_eq!(
+        audio::spatial_gain(Vec2::ZERO, Vec2::new(0.0, 2000.0)).0,
+        0.0
+    );
+}
+
+#[derive(Resource)]
+struct TestMusic;
+
+#[test]
+fn entering_the_game_crossfades_from_the_menu_music() {
+    let audio = AudioChannel::<TestMusic>::default();
+    let mut instances = Assets::<AudioInstance>::default();
+    let fades = MusicFades::default();
+    let mut director = MusicDirector::default();
+    // Each state plays the first file of its music. Returns the track faded out and the one
+    // playing afterwards
+    let mut enter = |tracks: &[&str]| {
+        let playlist = vec![(tracks[0].to_string(), Handle::default())];
+        let faded_out = director.crossfade_to(playlist, &audio, &mut instances, &fades);
+        (faded_out, director.playing().map(str::to_string))
+    };
+    let track = |tracks: &[&str]| Some(tracks[0].to_string());
+
+    assert_eq!(enter(MENU_MUSIC), (None, track(MENU_MUSIC)));
+    assert_eq!(enter(GAME_MUSIC), (track(MENU_MUSIC), track(GAME_MUSIC)));
+    // Staying on the same music does not restart it
+    assert_eq!(enter(GAME_MUSIC), (None, track(GAME_MUSIC)));
+    assert_eq!(enter(MENU_MUSIC), (track(GAME_MUSIC), track(MENU_MUSIC)));
+}
@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+use game::cli::Args;
+
+fn parse(args: &[&str]) -> Result<Args, String> {
+    Args::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn arguments_are_parsed() {
+    let args = parse(&[
+        "--seed",
+        "42",
+        "--level",
+        "levels/test.level",
+        "--skip-splash",
+        "--fullscreen",
+        "--mute",
+    ])
+    .unwrap();
+    assert_eq!(args.seed, Some(42));
+    assert_eq!(args.level, Some(PathBuf::from("levels/test.level")));
+    assert!(args.skip_splash && args.fullscreen && args.mute);
+    assert!(!args.headless);
+    assert!(args.starts_in_game());
+
+    // The last display mode given wins
+    assert!(!parse(&["--fullscreen", "--windowed"]).unwrap().fullscreen);
+    assert_eq!(parse(&[]).unwrap(), Args::default());
+}
+
+#[test]
+fn bad_arguments_are_rejected() {
+    assert!(parse(&["--seed"]).is_err());
+    assert!(parse(&["--seed", "abc"]).is_err());
+    assert!(parse(&["--level"]).is_err());
+    assert!(parse(&["--fast"]).is_err());
+}
+
+#[test]
+fn headless_runs_need_a_replay() {
+    assert!(parse(&["--headless"]).is_err());
+    assert!(parse(&["--headless", "--replay"]).is_err());
+
+    let args = parse(&["--headless", "--replay", "replays/last.replay"]).unwrap();
+    assert_eq!(args.replay, Some(PathBuf::from("replays/last.replay")));
+    assert!(args.headless && args.starts_in_game());
+
+    // A replay brings its own level
+    assert!(parse(&["--replay", "replays/last.replay", "--seed", "42"]).is_err());
+}
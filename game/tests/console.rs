@@ -0,0 +1,36 @@
+use bevy::prelude::*;
+
+use game::debug::ConsoleCommand;
+
+#[test]
+fn console_commands_are_parsed() {
+    assert_eq!(
+        ConsoleCommand::parse("spawn zombie"),
+        Ok(ConsoleCommand::SpawnZombie)
+    );
+    assert_eq!(
+        ConsoleCommand::parse("  teleport 10 -20.5 "),
+        Ok(ConsoleCommand::Teleport(Vec2::new(10.0, -20.5)))
+    );
+    assert_eq!(
+        ConsoleCommand::parse("godmode"),
+        Ok(ConsoleCommand::GodMode)
+    );
+    assert_eq!(
+        ConsoleCommand::parse("seed"),
+        Ok(ConsoleCommand::Seed(None))
+    );
+    assert_eq!(
+        ConsoleCommand::parse("seed 42"),
+        Ok(ConsoleCommand::Seed(Some(42)))
+    );
+    assert_eq!(ConsoleCommand::parse("win"), Ok(ConsoleCommand::Win));
+}
+
+#[test]
+fn bad_console_commands_are_rejected() {
+    assert!(ConsoleCommand::parse("teleport here").is_err());
+    assert!(ConsoleCommand::parse("teleport 1 two").is_err());
+    assert!(ConsoleCommand::parse("seed -1").is_err());
+    assert!(ConsoleCommand::parse("spawn dragon").is_err());
+}
@@ -0,0 +1,349 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use game::{
+    environment::{NextLevelSeed, Zombie},
+    events::{PlayerHitByEnemy, PlayerReachedFlag},
+    headless::{headless_app, HeadlessPlugin},
+    input::{Action, InputScript, InputSource, PlayerStick},
+    physics::Layer,
+    player::{Dash, GodMode, PlayerMovement, PlayerStatus},
+    GameState, OnGameScreen, PlayerFlag, WinFlag,
+};
+
+fn run_frames(app: &mut App, frames: usize) {
+    for _ in 0..frames {
+        app.update();
+    }
+}
+
+fn player_entity(app: &mut App) -> Entity {
+    app.world
+        .query_filtered::<Entity, With<PlayerFlag>>()
+        .single(&app.world)
+}
+
+fn spawn_zombie_at(app: &mut App, position: Vec3) -> Entity {
+    app.world
+        .spawn((
+            TransformBundle::from_transform(Transform::from_translation(position)),
+            Zombie,
+            RigidBody::KinematicVelocityBased,
+            Collider::cuboid(12.5, 12.5),
+            Layer::Enemy.groups(),
+        ))
+        .id()
+}
+
+#[test]
+fn level_and_player_are_spawned() {
+    let mut app = headless_app();
+
+    assert_eq!(
+        *app.world.resource::<State<GameState>>().get(),
+        GameState::Game
+    );
+    player_entity(&mut app);
+    assert_eq!(
+        app.world
+            .query_filtered::<Entity, With<WinFlag>>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+}
+
+fn level_layout(seed: u64) -> Vec<Vec3> {
+    let mut app = App::new();
+    app.add_plugins(HeadlessPlugin)
+        .insert_resource(NextLevelSeed(Some(seed)));
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Game);
+    app.update();
+
+    app.world
+        .query_filtered::<&Transform, With<OnGameScreen>>()
+        .iter(&app.world)
+        .map(|transform| transform.translation)
+        .collect()
+}
+
+#[test]
+fn same_seed_generates_same_level() {
+    assert_eq!(level_layout(42), level_layout(42));
+    assert_ne!(level_layout(42), level_layout(43));
+}
+
+#[test]
+fn player_moves_when_key_is_held() {
+    let mut app = headless_app();
+    let player = player_entity(&mut app);
+    let start = app.world.get::<Transform>(player).unwrap().translation;
+
+    // Obstacles are never placed in line with the starting spot, so moving down is not blocked
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::S);
+    run_frames(&mut app, 10);
+
+    let end = app.world.get::<Transform>(player).unwrap().translation;
+    assert!(end.y < start.y);
+}
+
+#[test]
+fn scripted_input_drives_the_player() {
+    let mut app = headless_app();
+    let player = player_entity(&mut app);
+    let start = app.world.get::<Transform>(player).unwrap().translation;
+
+    app.insert_resource(InputScript::new().hold(0, 5, Action::MoveDown))
+        .insert_resource(InputSource::Script);
+    run_frames(&mut app, 8);
+    assert!(app.world.resource::<InputScript>().is_finished());
+
+    let released = app.world.get::<Transform>(player).unwrap().translation;
+    assert!(released.y < start.y);
+
+    // Once the key is released the player slows down to a stop and stays there
+    run_frames(&mut app, 10);
+    let stopped = app.world.get::<Transform>(player).unwrap().translation;
+    assert!(stopped.y <= released.y);
+    run_frames(&mut app, 5);
+    assert_eq!(
+        app.world.get::<Transform>(player).unwrap().translation,
+        stopped
+    );
+}
+
+// Movement that reaches full speed and stops within a single frame
+fn instant_movement() -> PlayerMovement {
+    PlayerMovement {
+        acceleration: f32::INFINITY,
+        deceleration: f32::INFINITY,
+        ..default()
+    }
+}
+
+#[test]
+fn diagonal_moves_are_not_faster() {
+    let mut app = headless_app();
+    let player = player_entity(&mut app);
+    app.insert_resource(instant_movement())
+        .insert_resource(InputSource::Script);
+
+    let mut distance = |actions: &[Action]| {
+        let start = app.world.get::<Transform>(player).unwrap().translation;
+        let script = actions.iter().fold(InputScript::new(), |script, action| {
+            script.hold(0, 3, *action)
+        });
+        app.insert_resource(script);
+        run_frames(&mut app, 3);
+        (app.world.get::<Transform>(player).unwrap().translation - start).length()
+    };
+    let straight = distance(&[Action::MoveUp]);
+    let diagonal = distance(&[Action::MoveUp, Action::MoveLeft]);
+    assert!(straight > 0.0);
+    assert!((diagonal - straight).abs() < 0.5);
+}
+
+#[test]
+fn player_speeds_up_and_slows_down() {
+    let mut app = headless_app();
+    let player = player_entity(&mut app);
+    let max_speed = app.world.resource::<PlayerMovement>().max_speed;
+
+    // The column above the starting spot is kept clear, while the floor is right below it
+    let distance = |app: &mut App| {
+        let start = app.world.get::<Transform>(player).unwrap().translation;
+        run_frames(app, 3);
+        app.world.get::<Transform>(player).unwrap().translation.y - start.y
+    };
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::W);
+    let starting = distance(&mut app);
+    run_frames(&mut app, 20);
+    let full = distance(&mut app);
+    assert!(starting < full);
+    assert!((full - max_speed * 3.0 / 60.0).abs() < 0.1);
+
+    app.world
+        .resource_mut::<Input<KeyCode>>()
+        .release(KeyCode::W);
+    let slowing = distance(&mut app);
+    assert!(slowing > 0.0 && slowing < full);
+}
+
+#[test]
+fn stick_tilt_sets_the_speed() {
+    let mut app = headless_app();
+    let player = player_entity(&mut app);
+    // The live gamepad would reset the stick every frame
+    app.insert_resource(InputSource::Script)
+        .insert_resource(instant_movement());
+
+    let mut distance = |tilt: f32| {
+        let start = app.world.get::<Transform>(player).unwrap().translation;
+        app.insert_resource(PlayerStick(Vec2::new(0.0, -tilt)));
+        run_frames(&mut app, 5);
+        start.y - app.world.get::<Transform>(player).unwrap().translation.y
+    };
+    let half = distance(0.5);
+    let full = distance(1.0);
+    assert!(half > 0.0);
+    assert!(full > half * 1.5);
+}
+
+#[test]
+fn player_reaching_the_flag_wins() {
+    let mut app = headless_app();
+    let player = player_entity(&mut app);
+    let flag = app
+        .world
+        .query_filtered::<&Transform, With<WinFlag>>()
+        .single(&app.world)
+        .translation;
+
+    let mut reached = app
+        .world
+        .resource::<Events<PlayerReachedFlag>>()
+        .get_reader();
+    app.world.get_mut::<Transform>(player).unwrap().translation = flag;
+    let mut reached_flag = false;
+    for _ in 0..5 {
+        app.update();
+        let events = app.world.resource::<Events<PlayerReachedFlag>>();
+        reached_flag |= reached.read(events).count() > 0;
+    }
+    assert!(reached_flag);
+    assert_eq!(*app.world.resource::<PlayerStatus>(), PlayerStatus::Paused);
+
+    // The win screen stays up for three seconds before going back to the menu
+    run_frames(&mut app, 200);
+    assert_eq!(
+        *app.world.resource::<State<GameState>>().get(),
+        GameState::Menu
+    );
+}
+
+#[test]
+fn zombie_contact_is_reported() {
+    let mut app = headless_app();
+    let player = player_entity(&mut app);
+    let position = app.world.get::<Transform>(player).unwrap().translation;
+
+    let zombie = spawn_zombie_at(&mut app, position);
+
+    let mut hits = app
+        .world
+        .resource::<Events<PlayerHitByEnemy>>()
+        .get_reader();
+    let mut hit = false;
+    let mut hit_by_zombie = false;
+    for _ in 0..5 {
+        app.update();
+        hit_by_zombie |= hits
+            .read(app.world.resource::<Events<PlayerHitByEnemy>>())
+            .any(|event| event.enemy == zombie);
+        let events = app.world.resource::<Events<CollisionEvent>>();
+        hit |= events
+            .iter_current_update_events()
+            .any(|event| match event {
+                CollisionEvent::Started(a, b, _) => {
+                    (*a == player && *b == zombie) || (*a == zombie && *b == player)
+                }
+                CollisionEvent::Stopped(..) => false,
+            });
+    }
+    assert!(hit);
+    assert!(hit_by_zombie);
+
+    // Getting caught ends the run
+    assert_eq!(*app.world.resource::<PlayerStatus>(), PlayerStatus::Paused);
+}
+
+#[test]
+fn walking_into_a_zombie_ends_the_run() {
+    let mut app = headless_app();
+    let player = player_entity(&mut app);
+    let position = app.world.get::<Transform>(player).unwrap().translation;
+    app.insert_resource(InputScript::new().hold(0, 30, Action::MoveUp))
+        .insert_resource(InputSource::Script);
+
+    // The zombie stands still in the column kept clear above the start
+    spawn_zombie_at(&mut app, position + Vec3::Y * 40.0);
+    run_frames(&mut app, 30);
+
+    assert_eq!(*app.world.resource::<PlayerStatus>(), PlayerStatus::Paused);
+}
+
+#[test]
+fn god_mode_survives_zombies() {
+    let mut app = headless_app();
+    app.insert_resource(GodMode(true));
+    let player = player_entity(&mut app);
+    let position = app.world.get::<Transform>(player).unwrap().translation;
+
+    spawn_zombie_at(&mut app, position);
+    run_frames(&mut app, 5);
+
+    assert_eq!(*app.world.resource::<PlayerStatus>(), PlayerStatus::Active);
+}
+
+#[test]
+fn dash_moves_quickly_then_cools_down() {
+    let mut app = headless_app();
+    let player = player_entity(&mut app);
+    app.insert_resource(InputSource::Script);
+
+    let distance = |app: &mut App, script: InputScript, frames: usize| {
+        let start = app.world.get::<Transform>(player).unwrap().translation;
+        app.insert_resource(script);
+        run_frames(app, frames);
+        app.world.get::<Transform>(player).unwrap().translation.y - start.y
+    };
+    // The dash goes the way the player last moved, up the column kept clear above the start
+    let dashed = distance(
+        &mut app,
+        InputScript::new()
+            .hold(0, 1, Action::MoveUp)
+            .hold(1, 3, Action::Dash),
+        20,
+    );
+    assert!(dashed > 60.0);
+    assert!(!app.world.get::<Dash>(player).unwrap().is_dashing());
+
+    // Pressing again right away does nothing until the cooldown is over
+    let walked = distance(
+        &mut app,
+        InputScript::new()
+            .hold(0, 10, Action::MoveUp)
+            .hold(2, 4, Action::Dash),
+        10,
+    );
+    assert!(walked < 30.0);
+}
+
+#[test]
+fn dash_survives_zombies() {
+    let mut app = headless_app();
+    let player = player_entity(&mut app);
+    let position = app.world.get::<Transform>(player).unwrap().translation;
+    app.insert_resource(InputScript::new().hold(0, 2, Action::Dash))
+        .insert_resource(InputSource::Script);
+
+    spawn_zombie_at(&mut app, position);
+    run_frames(&mut app, 5);
+
+    assert!(app.world.get::<Dash>(player).unwrap().is_invulnerable());
+    assert_eq!(*app.world.resource::<PlayerStatus>(), PlayerStatus::Active);
+
+    // A zombie still on the player once the dash is over catches it when invulnerability ends
+    run_frames(&mut app, 7);
+    let position = app.world.get::<Transform>(player).unwrap().translation;
+    spawn_zombie_at(&mut app, position);
+    run_frames(&mut app, 2);
+    assert!(app.world.get::<Dash>(player).unwrap().is_invulnerable());
+    assert_eq!(*app.world.resource::<PlayerStatus>(), PlayerStatus::Active);
+
+    run_frames(&mut app, 10);
+    assert!(!app.world.get::<Dash>(player).unwrap().is_invulnerable());
+    assert_eq!(*app.world.resource::<PlayerStatus>(), PlayerStatus::Paused);
+}
@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+
+use game::input::{apply_deadzone, STICK_DEADZONE};
+
+#[test]
+fn stick_deadzone_is_taken_out() {
+    assert_eq!(
+        apply_deadzone(Vec2::new(0.1, 0.1), STICK_DEADZONE),
+        Vec2::ZERO
+    );
+    assert_eq!(apply_deadzone(Vec2::new(0.0, 1.0), STICK_DEADZONE), Vec2::Y);
+
+    // Right past the deadzone the player barely moves, in the direction of the stick
+    let tilted = apply_deadzone(Vec2::new(0.3, 0.0), STICK_DEADZONE);
+    assert!(tilted.x > 0.0 && tilted.x < 0.2);
+    assert_eq!(tilted.y, 0.0);
+
+    // Corners of square gates do not go faster than a full tilt
+    assert!(apply_deadzone(Vec2::ONE, STICK_DEADZONE).length() <= 1.0 + f32::EPSILON);
+}
@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+
+use game::{
+    headless::HeadlessPlugin,
+    level::{Block, BlockKind, CustomLevel, Level},
+    GameState, WinFlag,
+};
+
+#[test]
+fn generated_level_survives_a_round_trip() {
+    let level = Level::generate(42);
+    assert_eq!(Level::parse(&level.to_string()).unwrap(), level);
+}
+
+#[test]
+fn malformed_levels_are_rejected() {
+    assert!(Level::parse("obstacle 1 2").is_err());
+    assert!(Level::parse("passive-power-level 1\nobstacle 1 2").is_err());
+    assert!(Level::parse("passive-power-level 1\nflag 1\n").is_err());
+    assert!(Level::parse("passive-power-level 1\nflag 1 2\nboulder 3 4\n").is_err());
+}
+
+#[test]
+fn level_playlist_survives_a_round_trip() {
+    let level = Level::parse(
+        "passive-power-level 1\n\
+         flag 1 2\n\
+         music audios/calm.ogg\n\
+         music audios/boss theme.ogg\n",
+    )
+    .unwrap();
+    assert_eq!(
+        level.music,
+        vec!["audios/calm.ogg", "audios/boss theme.ogg"]
+    );
+    assert_eq!(Level::parse(&level.to_string()).unwrap(), level);
+    assert!(Level::generate(7).music.is_empty());
+}
+
+#[test]
+fn custom_level_is_played() {
+    let level = Level::parse(
+        "passive-power-level 1\n\
+         # Everything in a single row\n\
+         flag 300 0\n\
+         obstacle -100 0\n\
+         spawner 100 0\n",
+    )
+    .unwrap();
+    assert_eq!(
+        level.blocks,
+        vec![
+            Block {
+                kind: BlockKind::Obstacle,
+                position: Vec2::new(-100., 0.),
+            },
+            Block {
+                kind: BlockKind::Spawner,
+                position: Vec2::new(100., 0.),
+            },
+        ]
+    );
+
+    let mut app = App::new();
+    app.add_plugins(HeadlessPlugin)
+        .insert_resource(CustomLevel(level));
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Game);
+    app.update();
+
+    let flag = app
+        .world
+        .query_filtered::<&Transform, With<WinFlag>>()
+        .single(&app.world);
+    assert_eq!(flag.translation.truncate(), Vec2::new(300., 0.));
+}
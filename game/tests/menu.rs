@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+use game::menu::{next_focus, MenuInput};
+
+#[test]
+fn focus_moves_to_the_closest_button_ahead() {
+    // A column of three buttons, the middle one having a neighbour on its right
+    let top = Entity::from_raw(1);
+    let middle = Entity::from_raw(2);
+    let right = Entity::from_raw(3);
+    let bottom = Entity::from_raw(4);
+    let buttons = [
+        (top, Vec2::new(100., 0.)),
+        (middle, Vec2::new(100., 100.)),
+        (right, Vec2::new(300., 110.)),
+        (bottom, Vec2::new(100., 200.)),
+    ];
+    let from = Vec2::new(100., 100.);
+    let others = buttons.into_iter().filter(|(entity, _)| *entity != middle);
+
+    let down = MenuInput::Down.direction().unwrap();
+    assert_eq!(next_focus(from, down, others.clone()), Some(bottom));
+    let up = MenuInput::Up.direction().unwrap();
+    assert_eq!(next_focus(from, up, others.clone()), Some(top));
+    let right_direction = MenuInput::Right.direction().unwrap();
+    assert_eq!(
+        next_focus(from, right_direction, others.clone()),
+        Some(right)
+    );
+    let left = MenuInput::Left.direction().unwrap();
+    assert_eq!(next_focus(from, left, others), None);
+}
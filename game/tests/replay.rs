@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+
+use game::{
+    ghost::{GhostRun, GhostSample},
+    replay::{Replay, ReplayFrame},
+};
+
+#[test]
+fn replay_survives_a_round_trip() {
+    let replay = Replay {
+        seed: 1234,
+        frames: vec![
+            ReplayFrame::default(),
+            ReplayFrame {
+                keys: 0b1001,
+                stick: IVec2::ZERO,
+            },
+            ReplayFrame {
+                keys: 0,
+                stick: IVec2::new(-35, 100),
+            },
+        ],
+    };
+
+    assert_eq!(Replay::parse(&replay.to_string()).unwrap(), replay);
+    assert!(Replay::parse("seed 1234\n").is_err());
+    // Replays from before the stick was recorded are rejected
+    assert!(Replay::parse("passive-power-replay 3
+seed 1234
+16
+").is_err());
+}
+
+#[test]
+fn ghost_position_is_interpolated_between_samples() {
+    let ghost = GhostRun {
+        seed: 1234,
+        samples: vec![
+            GhostSample {
+                time: 0.5,
+                position: Vec2::new(0.0, 0.0),
+            },
+            GhostSample {
+                time: 1.5,
+                position: Vec2::new(10.0, -20.0),
+            },
+        ],
+    };
+
+    assert_eq!(GhostRun::parse(&ghost.to_string()).unwrap(), ghost);
+    assert_eq!(ghost.duration(), 1.5);
+    assert_eq!(ghost.position_at(0.0), Some(Vec2::new(0.0, 0.0)));
+    assert_eq!(ghost.position_at(1.0), Some(Vec2::new(5.0, -10.0)));
+    assert_eq!(ghost.position_at(3.0), Some(Vec2::new(10.0, -20.0)));
+}
@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+
+use game::{
+    input::{Action, Bindings},
+    settings::Settings,
+    DisplayQuality, Volume,
+};
+
+#[test]
+fn settings_survive_a_round_trip() {
+    let mut settings = Settings {
+        display_quality: DisplayQuality::High,
+        master: Volume(4),
+        muted: true,
+        ..default()
+    };
+    settings.bindings.bind_key(Action::Dash, KeyCode::Up);
+    settings
+        .bindings
+        .bind_button(Action::Pause, GamepadButtonType::North);
+    assert_eq!(Settings::parse(&settings.to_string()).unwrap(), settings);
+}
+
+#[test]
+fn malformed_settings_are_skipped() {
+    assert!(Settings::parse("display high").is_err());
+
+    let settings =
+        Settings::parse("passive-power-settings 1\nmaster loud\nkey jump Space\nmusic 3\n")
+            .unwrap();
+    assert_eq!(settings.master, Settings::default().master);
+    assert_eq!(settings.music, Volume(3));
+    assert_eq!(
+        Settings::parse("passive-power-settings 1\nkey dash Spacebar\n").unwrap(),
+        Settings::default()
+    );
+}
+
+#[test]
+fn only_changed_settings_are_taken() {
+    let mut saved = Settings {
+        music: Volume(2),
+        ..default()
+    };
+    // Muted on the command line, then the master volume is turned down in the menus
+    let before = Settings {
+        muted: true,
+        ..saved.clone()
+    };
+    let after = Settings {
+        master: Volume(5),
+        ..before.clone()
+    };
+    saved.take_changes(&before, &after);
+    assert!(!saved.muted);
+    assert_eq!(saved.master, Volume(5));
+    assert_eq!(saved.music, Volume(2));
+}
+
+#[test]
+fn rebinding_a_taken_key_swaps_the_actions() {
+    let mut bindings = Bindings::default();
+    bindings.bind_key(Action::MoveUp, KeyCode::S);
+    assert_eq!(bindings.key(Action::MoveUp), Some(KeyCode::S));
+    assert_eq!(bindings.key(Action::MoveDown), Some(KeyCode::W));
+}
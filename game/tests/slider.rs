@@ -0,0 +1,33 @@
+use game::{
+    menu::{Slider, SliderSetting},
+    MasterVolume, Volume,
+};
+
+#[test]
+fn slider_stays_within_its_range() {
+    let mut slider = Slider::new(9, Volume::MAX);
+    slider.step(1);
+    slider.step(1);
+    assert_eq!(slider.value, 10);
+
+    slider.step(-20);
+    assert_eq!(slider.value, 0);
+}
+
+#[test]
+fn dragging_snaps_to_the_closest_step() {
+    let mut slider = Slider::new(0, Volume::MAX);
+    slider.set_from_cursor(0.44);
+    assert_eq!(slider.value, 4);
+
+    slider.set_from_cursor(1.3);
+    assert_eq!(slider.value, 10);
+    assert_eq!(slider.fraction(), 1.0);
+}
+
+#[test]
+fn full_volume_can_be_picked() {
+    let volume = MasterVolume::from_value(Volume::MAX);
+    assert_eq!(volume.get_val(), 1.0);
+    assert_eq!(volume.value(), Volume::MAX);
+}